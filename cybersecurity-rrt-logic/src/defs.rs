@@ -13,6 +13,18 @@ pub struct Operator {
 }
 
 impl Operator {
+    pub fn operator(&self) -> OperatorType {
+        self.operator
+    }
+    /// total backtrace value the operator can handle normally
+    pub fn normal_track(&self) -> u8 {
+        self.normal_track
+    }
+    /// total backtrace value the operator can handle in desperation mode
+    pub fn desperation_track(&self) -> u8 {
+        self.desperation_track
+    }
+
     const STONE: Operator = Operator {
         operator: Stone,
         normal_track: 9,
@@ -92,6 +104,23 @@ pub enum OperatorType {
     Admin,
 }
 
+/// index in defs::OPERATOR_TYPES
+pub type OperatorTypeID = u8;
+pub static OPERATOR_TYPES: [OperatorType; 7] = [Stone, Sniper, Rogue, Biggs, Rich, Charm, Admin];
+
+impl OperatorType {
+    /// Capabilities of this operator's skill
+    pub fn skill_tags(&self) -> &'static [AbilityTag] {
+        match self {
+            Stone => &[AbilityTag::DiscardsHacker],
+            Sniper | Admin => &[AbilityTag::IgnoresPenalty],
+            Rogue => &[AbilityTag::ExtraAction],
+            Biggs | Charm => &[AbilityTag::TargetsNeighbor],
+            Rich => &[AbilityTag::ModifiesDeck],
+        }
+    }
+}
+
 pub fn operator(operator: &OperatorType) -> Operator {
     match operator {
         Stone => Operator::STONE,
@@ -107,6 +136,8 @@ pub fn operator(operator: &OperatorType) -> Operator {
 /// Definition of a particular hacker
 pub struct Hacker {
     value: u8,
+    /// if this hacker is in the breach at the end of the round, it compromises
+    /// infrastructure when the breach is resolved
     virus: bool,
     penalty: Penalty,
     symbol: Symbol,
@@ -130,17 +161,77 @@ impl Hacker {
 /// Symbol on top right of hackers, which operators
 /// need to secure one of each by end of turn in order
 /// to not suffer consequences
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Symbol {
     NoSymbol,
     Keyboard,
     Webservice,
     Database,
 }
+impl Symbol {
+    /// Index of the secure slot hackers with this symbol are secured in, or None if
+    /// they cannot be secured.
+    pub fn secure_slot(&self) -> Option<usize> {
+        match self {
+            NoSymbol => None,
+            Keyboard => Some(0),
+            Webservice => Some(1),
+            Database => Some(2),
+        }
+    }
+
+    /// How to draw this symbol without relying on color
+    pub fn alt_encoding(&self) -> AltEncoding {
+        match self {
+            NoSymbol => AltEncoding::new("blank", 0),
+            Keyboard => AltEncoding::new("square", 1),
+            Webservice => AltEncoding::new("circle", 2),
+            Database => AltEncoding::new("triangle", 3),
+        }
+    }
+}
+
 /// index in defs::SYMBOLS
 pub type SymbolID = u8;
 pub static SYMBOLS: [Symbol; 4] = [NoSymbol, Keyboard, Webservice, Database];
 
+/// Colorblind friendly way of telling apart symbols (or ability tags) - each one in
+/// a group has a different shape and fill pattern.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AltEncoding {
+    shape: &'static str,
+    pattern: PatternID,
+}
+
+impl AltEncoding {
+    const fn new(shape: &'static str, pattern: PatternID) -> AltEncoding {
+        AltEncoding { shape, pattern }
+    }
+    /// name of the outline shape, e.g. "circle"
+    pub fn shape(&self) -> &'static str {
+        self.shape
+    }
+    pub fn pattern(&self) -> PatternID {
+        self.pattern
+    }
+}
+
+/// index in defs::PATTERNS
+pub type PatternID = u8;
+/// Names of the fill patterns
+pub static PATTERNS: [&str; 8] = [
+    "solid",
+    "stripes",
+    "dots",
+    "crosshatch",
+    "checker",
+    "waves",
+    "zigzag",
+    "grid",
+];
+
 /// Penalties which enemies can inflict
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Penalty {
     NoPenalty,
     /// Compromise a firewall, or webservice if no firewalls left.
@@ -149,9 +240,12 @@ pub enum Penalty {
     Burnout,
     /// Place top card of Hacker stack on Breach stack, unseen
     Ninja,
-    /// operator cannot Secure (place to left of their board) during their turn
+    /// operator cannot Secure (place to left of their board) during their turns. Lasts
+    /// until they idle, as idling is what frees an operator from the penalty of the last
+    /// hacker in their backtrace list - not just for the rest of the turn.
     NoSecure,
-    /// operator may not perform Assist action, but can receive it.
+    /// operator may not perform Assist action, but can receive it. Lasts until they idle,
+    /// like NoSecure.
     NoGiveAssist,
     /// operator to left must draw a hacker and add to their backtrace list.
     DrawLeft,
@@ -173,6 +267,195 @@ pub enum Penalty {
     Idle,
 }
 
+/// index in defs::PENALTIES
+pub type PenaltyID = u8;
+pub static PENALTIES: [Penalty; 15] = [
+    NoPenalty,
+    Compromise,
+    Burnout,
+    Ninja,
+    NoSecure,
+    NoGiveAssist,
+    DrawLeft,
+    DrawRight,
+    DoubleCompromise,
+    NoSecureAndHackerRevive,
+    NoGiveAssistAndBurnout,
+    DiscardSecure,
+    NoTalentAndBurnout,
+    DoubleNinja,
+    Idle,
+];
+
+impl Penalty {
+    /// Capabilities of this penalty
+    pub fn tags(&self) -> &'static [AbilityTag] {
+        match self {
+            NoPenalty => &[],
+            Compromise | DoubleCompromise => &[AbilityTag::InfraDamage],
+            Burnout | DiscardSecure => &[AbilityTag::OperatorDamage],
+            Ninja | DoubleNinja => &[AbilityTag::ModifiesDeck],
+            NoSecure | NoGiveAssist | Idle => &[AbilityTag::BlocksAction],
+            DrawLeft | DrawRight => &[AbilityTag::TargetsNeighbor, AbilityTag::ModifiesDeck],
+            NoSecureAndHackerRevive => &[AbilityTag::BlocksAction, AbilityTag::ModifiesDeck],
+            NoGiveAssistAndBurnout | NoTalentAndBurnout => {
+                &[AbilityTag::BlocksAction, AbilityTag::OperatorDamage]
+            }
+        }
+    }
+
+    /// Keywords which appear in this penalty's effect text
+    pub fn keywords(&self) -> &'static [Keyword] {
+        match self {
+            NoPenalty => &[],
+            Compromise | DoubleCompromise => {
+                &[Keyword::Compromise, Keyword::Firewall, Keyword::Webservice]
+            }
+            Burnout => &[Keyword::Burnout],
+            Ninja | DoubleNinja => &[Keyword::HackerStack, Keyword::Breach],
+            NoSecure => &[Keyword::Secure],
+            NoGiveAssist => &[Keyword::Assist],
+            DrawLeft | DrawRight => &[Keyword::HackerStack, Keyword::Backtrace],
+            NoSecureAndHackerRevive => &[Keyword::Secure, Keyword::Discard, Keyword::HackerStack],
+            NoGiveAssistAndBurnout => &[Keyword::Assist, Keyword::Burnout],
+            DiscardSecure => &[Keyword::Discard, Keyword::Secure],
+            NoTalentAndBurnout => &[Keyword::Burnout, Keyword::Skill, Keyword::Assist],
+            Idle => &[Keyword::Idle],
+        }
+    }
+}
+
+/// Machine readable capabilities of penalties and skills, so frontends can pick
+/// generic animations and icons, and stats can be aggregated by kind of effect.
+#[derive(Copy, Clone, PartialEq, Debug, Hash, Eq)]
+pub enum AbilityTag {
+    /// affects an operator other than the one who triggered it
+    TargetsNeighbor,
+    /// moves cards between the hacker stack, breach or discard
+    ModifiesDeck,
+    /// prevents an operator from taking some action
+    BlocksAction,
+    /// compromises firewalls, webservices or databases
+    InfraDamage,
+    /// burns out an operator or takes away what they secured
+    OperatorDamage,
+    /// removes a hacker from play
+    DiscardsHacker,
+    /// prevents a penalty from applying
+    IgnoresPenalty,
+    /// grants an additional action
+    ExtraAction,
+}
+
+impl AbilityTag {
+    /// How to draw this tag without relying on color
+    pub fn alt_encoding(&self) -> AltEncoding {
+        match self {
+            AbilityTag::TargetsNeighbor => AltEncoding::new("arrow", 0),
+            AbilityTag::ModifiesDeck => AltEncoding::new("rectangle", 1),
+            AbilityTag::BlocksAction => AltEncoding::new("octagon", 2),
+            AbilityTag::InfraDamage => AltEncoding::new("triangle", 3),
+            AbilityTag::OperatorDamage => AltEncoding::new("cross", 4),
+            AbilityTag::DiscardsHacker => AltEncoding::new("circle", 5),
+            AbilityTag::IgnoresPenalty => AltEncoding::new("shield", 6),
+            AbilityTag::ExtraAction => AltEncoding::new("star", 7),
+        }
+    }
+}
+
+/// Game terms which appear in effect text, so every frontend can highlight
+/// them and explain them the same way.
+#[derive(Copy, Clone, PartialEq, Debug, Hash, Eq)]
+pub enum Keyword {
+    Secure,
+    Backtrace,
+    Breach,
+    Burnout,
+    Firewall,
+    Database,
+    Webservice,
+    Compromise,
+    Face,
+    Assist,
+    Idle,
+    Flow,
+    Skill,
+    Desperation,
+    Discard,
+    HackerStack,
+}
+
+/// index in defs::KEYWORDS
+pub type KeywordID = u8;
+pub static KEYWORDS: [Keyword; 16] = [
+    Keyword::Secure,
+    Keyword::Backtrace,
+    Keyword::Breach,
+    Keyword::Burnout,
+    Keyword::Firewall,
+    Keyword::Database,
+    Keyword::Webservice,
+    Keyword::Compromise,
+    Keyword::Face,
+    Keyword::Assist,
+    Keyword::Idle,
+    Keyword::Flow,
+    Keyword::Skill,
+    Keyword::Desperation,
+    Keyword::Discard,
+    Keyword::HackerStack,
+];
+
+impl Keyword {
+    pub fn id(&self) -> KeywordID {
+        *self as KeywordID
+    }
+
+    /// Name of the keyword as it appears in effect text
+    pub fn name(&self) -> &'static str {
+        match self {
+            Keyword::Secure => "Secure",
+            Keyword::Backtrace => "Backtrace",
+            Keyword::Breach => "Breach",
+            Keyword::Burnout => "Burnout",
+            Keyword::Firewall => "Firewall",
+            Keyword::Database => "Database",
+            Keyword::Webservice => "Webservice",
+            Keyword::Compromise => "Compromise",
+            Keyword::Face => "Face",
+            Keyword::Assist => "Assist",
+            Keyword::Idle => "Idle",
+            Keyword::Flow => "Flow",
+            Keyword::Skill => "Skill",
+            Keyword::Desperation => "Desperation",
+            Keyword::Discard => "Discard",
+            Keyword::HackerStack => "Hacker stack",
+        }
+    }
+
+    /// Tooltip explaining the keyword
+    pub fn description(&self) -> &'static str {
+        match self {
+            Keyword::Secure => "Place a faced hacker in the empty secure slot matching its symbol, on the left of the operator's board.",
+            Keyword::Backtrace => "Place a faced hacker at the end of the backtrace list, on the right of the operator's board, suffering its penalty.",
+            Keyword::Breach => "Pile of hackers which got through this round unseen.",
+            Keyword::Burnout => "Token placed on an exhausted operator.",
+            Keyword::Firewall => "Protects the webservices - compromises take down firewalls first.",
+            Keyword::Database => "Critical infrastructure - losing all of them loses the game.",
+            Keyword::Webservice => "Infrastructure which is compromised once no firewalls are left.",
+            Keyword::Compromise => "Take down a firewall, or a webservice if no firewalls are left.",
+            Keyword::Face => "Draw the top hacker of the hacker stack and decide where to place it.",
+            Keyword::Assist => "Give an operator's assist token to another operator, who may then use that operator's skill.",
+            Keyword::Idle => "Do nothing for the remainder of the round.",
+            Keyword::Flow => "Operator-specific ability used when their backtrace list reaches their track length.",
+            Keyword::Skill => "Operator-specific ability, also usable by whoever holds their assist token.",
+            Keyword::Desperation => "Mode an operator enters after burning out, with a longer track and stronger flow.",
+            Keyword::Discard => "Pile of hackers removed from play.",
+            Keyword::HackerStack => "Facedown deck of hackers operators face.",
+        }
+    }
+}
+
 /// TODO: Could encapsulate this stuff better so we avoid out of bounds indexing.
 /// Index in defs::HACKERS; Note that NO_HACKER indicates no hacker and should not
 /// be used to index into HACKERS
@@ -0,0 +1,220 @@
+/// Choices annotated with why they are or aren't allowed, for teaching overlays which
+/// grey out illegal options rather than hiding them.
+use super::{Choice, ChoiceState, OperatorID, TableState};
+use crate::defs::{self, Symbol, NO_HACKER};
+
+/// Rule preventing a choice
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RuleViolation {
+    /// hacker stack is empty, so there is nothing to face
+    NoHackers,
+    /// operator already gave their assist token away this round
+    AssistTokenGiven,
+    /// a penalty prevents the operator from giving their assist token until they idle
+    NoGiveAssist,
+    /// operator being assisted is idle
+    TargetIdle,
+    /// a penalty prevents the operator from securing until they idle
+    NoSecure,
+    /// faced hacker has no symbol, so there is no slot to secure it in
+    NoSymbol,
+    /// secure slot for the faced hacker's symbol already has a hacker in it
+    SlotTaken,
+    /// none of the infrastructure is down
+    NothingToRepair,
+    /// operator has no secured hacker of the symbol to pay for the repair with
+    NoRepairHacker,
+}
+
+/// A choice which could be made in the current choice state, and what prevents it
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnnotatedChoice {
+    pub choice: Choice,
+    /// None if the choice is valid
+    pub violation: Option<RuleViolation>,
+}
+
+impl TableState {
+    /// Same as valid_choices, but also including the invalid choices an operator might
+    /// expect to be able to make, each with the rule preventing it. Choices only
+    /// offered by variants which aren't being played are left out.
+    pub fn annotated_choices(&self) -> Vec<AnnotatedChoice> {
+        match self.choice_state {
+            ChoiceState::ChooseAction(operator) => self.annotate_choose_action(operator),
+            ChoiceState::Face(operator) => self.annotate_face(operator),
+            _ => self
+                .valid_choices_iter()
+                .map(|choice| AnnotatedChoice {
+                    choice,
+                    violation: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn annotate_choose_action(&self, operator: OperatorID) -> Vec<AnnotatedChoice> {
+        let state = &self.operators[operator as usize];
+        let mut choices = vec![
+            annotate(Choice::Idle, None),
+            annotate(
+                Choice::Face,
+                self.hackers.is_empty().then_some(RuleViolation::NoHackers),
+            ),
+        ];
+        for (i, target) in self.operators.iter().enumerate() {
+            if i == operator as usize {
+                continue;
+            }
+            let violation = if !state.skills.contains(&state.operator) {
+                Some(RuleViolation::AssistTokenGiven)
+            } else if state.no_give_assist {
+                Some(RuleViolation::NoGiveAssist)
+            } else if target.idle {
+                Some(RuleViolation::TargetIdle)
+            } else {
+                None
+            };
+            choices.push(annotate(Choice::Assist(i as OperatorID), violation));
+        }
+        if self.options.repair {
+            let repairs = [
+                (
+                    Choice::RepairWebservice,
+                    Symbol::Webservice,
+                    &self.webservices[..],
+                ),
+                (
+                    Choice::RepairDatabase,
+                    Symbol::Database,
+                    &self.databases[..],
+                ),
+            ];
+            for (choice, symbol, infrastructure) in repairs {
+                let slot = symbol.secure_slot().unwrap();
+                let violation = if !infrastructure.contains(&false) {
+                    Some(RuleViolation::NothingToRepair)
+                } else if state.secure_slots[slot] == NO_HACKER {
+                    Some(RuleViolation::NoRepairHacker)
+                } else {
+                    None
+                };
+                choices.push(annotate(choice, violation));
+            }
+        }
+        choices
+    }
+
+    fn annotate_face(&self, operator: OperatorID) -> Vec<AnnotatedChoice> {
+        let state = &self.operators[operator as usize];
+        let slot = defs::hacker(self.facing).symbol().secure_slot();
+        let violation = if state.no_secure {
+            Some(RuleViolation::NoSecure)
+        } else {
+            match slot {
+                None => Some(RuleViolation::NoSymbol),
+                Some(x) if state.secure_slots[x] != NO_HACKER => Some(RuleViolation::SlotTaken),
+                Some(_) => None,
+            }
+        };
+        vec![
+            annotate(Choice::Secure, violation),
+            annotate(Choice::Backtrace, None),
+        ]
+    }
+}
+
+fn annotate(choice: Choice, violation: Option<RuleViolation>) -> AnnotatedChoice {
+    AnnotatedChoice { choice, violation }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Difficulty, GameConfig, RuleOptions};
+    use super::*;
+    use crate::defs::OperatorType::*;
+    use arrayvec::ArrayVec;
+    use spectral::prelude::*;
+
+    fn state() -> TableState {
+        let config = GameConfig::new(
+            Difficulty::Easy,
+            ArrayVec::from_iter([Stone, Sniper, Rogue]),
+        );
+        TableState::setup_game(&config.unwrap())
+    }
+
+    fn valid(state: &TableState) -> Vec<Choice> {
+        state
+            .annotated_choices()
+            .into_iter()
+            .filter(|x| x.violation.is_none())
+            .map(|x| x.choice)
+            .collect()
+    }
+
+    fn violation(state: &TableState, choice: Choice) -> Option<RuleViolation> {
+        let annotated = state.annotated_choices();
+        annotated
+            .into_iter()
+            .find(|x| x.choice == choice)
+            .unwrap()
+            .violation
+    }
+
+    #[test]
+    fn choose_action() {
+        let mut state = state();
+        state.operators[2].idle = true;
+        assert_that(&valid(&state)).is_equal_to(state.valid_choices());
+        assert_that(&violation(&state, Choice::Assist(2)))
+            .is_equal_to(Some(RuleViolation::TargetIdle));
+
+        state.hackers.clear();
+        state.operators[0].no_give_assist = true;
+        assert_that(&valid(&state)).is_equal_to(state.valid_choices());
+        assert_that(&violation(&state, Choice::Face)).is_equal_to(Some(RuleViolation::NoHackers));
+        assert_that(&violation(&state, Choice::Assist(1)))
+            .is_equal_to(Some(RuleViolation::NoGiveAssist));
+
+        state.operators[0].skills.clear();
+        assert_that(&violation(&state, Choice::Assist(1)))
+            .is_equal_to(Some(RuleViolation::AssistTokenGiven));
+    }
+
+    #[test]
+    fn repairs_only_with_variant() {
+        let mut state = state();
+        assert!(state
+            .annotated_choices()
+            .iter()
+            .all(|x| x.choice != Choice::RepairDatabase));
+
+        state.options = RuleOptions {
+            repair: true,
+            ..RuleOptions::default()
+        };
+        assert_that(&violation(&state, Choice::RepairDatabase))
+            .is_equal_to(Some(RuleViolation::NothingToRepair));
+        state.databases[0] = false;
+        assert_that(&violation(&state, Choice::RepairDatabase))
+            .is_equal_to(Some(RuleViolation::NoRepairHacker));
+        state.operators[0].secure_slots[2] = 3;
+        assert_that(&violation(&state, Choice::RepairDatabase)).is_none();
+        assert_that(&valid(&state)).is_equal_to(state.valid_choices());
+    }
+
+    #[test]
+    fn face() {
+        let mut state = state();
+        state.choice_state = ChoiceState::Face(0);
+        state.facing = 12;
+        assert_that(&violation(&state, Choice::Secure)).is_equal_to(Some(RuleViolation::NoSymbol));
+        state.facing = 3;
+        assert_that(&violation(&state, Choice::Secure)).is_none();
+        state.operators[0].secure_slots[2] = 16;
+        assert_that(&violation(&state, Choice::Secure)).is_equal_to(Some(RuleViolation::SlotTaken));
+        state.operators[0].no_secure = true;
+        assert_that(&violation(&state, Choice::Secure)).is_equal_to(Some(RuleViolation::NoSecure));
+        assert_that(&valid(&state)).is_equal_to(state.valid_choices());
+    }
+}
@@ -0,0 +1,173 @@
+/// Counters of which rules games actually exercise, so rarely used rules can be
+/// targeted by testing and balance work.
+use super::{ChoiceState, TableState};
+use crate::defs::{OperatorType, Penalty, OPERATOR_TYPES, PENALTIES};
+
+/// Names of the kinds of choice state, index in Coverage.choice_states
+static CHOICE_STATES: [&str; 17] = [
+    "Flow",
+    "CharmDesperationFlow",
+    "BiggsFlow",
+    "BiggsDesperationFlow",
+    "Face",
+    "Skill",
+    "DiscardLeft",
+    "ChooseAction",
+    "Webservice",
+    "GameOver",
+    "Pass",
+    "Reveal",
+    "StoneFlow",
+    "RogueFlow",
+    "RogueDesperationFlow",
+    "BiggsFlowTarget",
+    "RichFlow",
+];
+
+/// How many times each rule was exercised
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Coverage {
+    /// index in defs::PENALTIES
+    penalties: [u32; 15],
+    /// skills used, index in defs::OPERATOR_TYPES
+    skills: [u32; 7],
+    /// flows used, index in defs::OPERATOR_TYPES
+    flows: [u32; 7],
+    /// choice states entered, index in CHOICE_STATES
+    choice_states: [u32; 17],
+}
+
+impl Coverage {
+    pub(super) fn penalty(&mut self, penalty: Penalty) {
+        self.penalties[penalty as usize] += 1;
+    }
+
+    pub(super) fn skill(&mut self, skill: OperatorType) {
+        self.skills[skill as usize] += 1;
+    }
+
+    pub(super) fn flow(&mut self, operator: OperatorType) {
+        self.flows[operator as usize] += 1;
+    }
+
+    pub(super) fn choice_state(&mut self, choice_state: &ChoiceState) {
+        let idx = match choice_state {
+            ChoiceState::Flow(_) => 0,
+            ChoiceState::CharmDesperationFlow => 1,
+            ChoiceState::BiggsFlow => 2,
+            ChoiceState::BiggsDesperationFlow => 3,
+            ChoiceState::Face(_) => 4,
+            ChoiceState::Skill(_) => 5,
+            ChoiceState::DiscardLeft(_) => 6,
+            ChoiceState::ChooseAction(_) => 7,
+            ChoiceState::Webservice(_, _) => 8,
+            ChoiceState::GameOver => 9,
+            ChoiceState::Pass(_) => 10,
+            ChoiceState::Reveal(_, _) => 11,
+            ChoiceState::StoneFlow => 12,
+            ChoiceState::RogueFlow(_) => 13,
+            ChoiceState::RogueDesperationFlow => 14,
+            ChoiceState::BiggsFlowTarget(_) => 15,
+            ChoiceState::RichFlow(_) => 16,
+        };
+        self.choice_states[idx] += 1;
+    }
+
+    /// Adds the counts of another game, for aggregating a batch of games
+    pub fn merge(&mut self, other: &Coverage) {
+        let pairs = [
+            (&mut self.penalties[..], &other.penalties[..]),
+            (&mut self.skills[..], &other.skills[..]),
+            (&mut self.flows[..], &other.flows[..]),
+            (&mut self.choice_states[..], &other.choice_states[..]),
+        ];
+        for (counts, other) in pairs {
+            for (count, x) in counts.iter_mut().zip(other) {
+                *count += x;
+            }
+        }
+    }
+
+    /// Every counter with its name, including those which are still 0
+    pub fn report(&self) -> Vec<(String, u32)> {
+        let mut report = Vec::new();
+        for (penalty, count) in PENALTIES.iter().zip(self.penalties) {
+            report.push((format!("penalty_{:?}", penalty), count));
+        }
+        for (operator, count) in OPERATOR_TYPES.iter().zip(self.skills) {
+            report.push((format!("skill_{:?}", operator), count));
+        }
+        for (operator, count) in OPERATOR_TYPES.iter().zip(self.flows) {
+            report.push((format!("flow_{:?}", operator), count));
+        }
+        for (name, count) in CHOICE_STATES.iter().zip(self.choice_states) {
+            report.push((format!("choice_state_{}", name), count));
+        }
+        report
+    }
+}
+
+impl TableState {
+    /// Start counting which rules are exercised from now on. Off by default, as
+    /// most games have no use for it.
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(Coverage::default());
+    }
+
+    /// Rules exercised since enable_coverage, or None if it wasn't called
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Choice, Difficulty, GameConfig};
+    use super::*;
+    use crate::defs::OperatorType::*;
+    use arrayvec::ArrayVec;
+    use spectral::prelude::*;
+
+    fn state() -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Biggs, Charm]));
+        TableState::setup_game(&config.unwrap())
+    }
+
+    fn count(coverage: &Coverage, name: &str) -> u32 {
+        coverage.report().iter().find(|(x, _)| x == name).unwrap().1
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut state = state();
+        state.choose(Choice::Idle).unwrap();
+        assert!(state.coverage().is_none());
+    }
+
+    #[test]
+    fn counts_choice_states() {
+        let mut state = state();
+        state.enable_coverage();
+        state.apply_all([Choice::Assist(1), Choice::Face]).unwrap();
+        let coverage = state.coverage().unwrap();
+        assert_that(&count(coverage, "choice_state_ChooseAction")).is_equal_to(1);
+        let faced = count(coverage, "choice_state_Face") + count(coverage, "choice_state_Skill");
+        assert_that(&faced).is_equal_to(1);
+        assert_that(&count(coverage, "choice_state_GameOver")).is_equal_to(0);
+    }
+
+    #[test]
+    fn merge() {
+        let mut total = Coverage::default();
+        total.penalty(Penalty::Ninja);
+        let mut other = Coverage::default();
+        other.penalty(Penalty::Ninja);
+        other.flow(Sniper);
+        other.choice_state(&ChoiceState::GameOver);
+        total.merge(&other);
+        assert_that(&count(&total, "penalty_Ninja")).is_equal_to(2);
+        assert_that(&count(&total, "flow_Sniper")).is_equal_to(1);
+        assert_that(&count(&total, "choice_state_GameOver")).is_equal_to(1);
+        assert_that(&total.report().len()).is_equal_to(15 + 7 + 7 + 17);
+    }
+}
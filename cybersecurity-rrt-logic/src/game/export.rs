@@ -0,0 +1,167 @@
+/// Flattening of table state into a single wide row, for ad-hoc analysis in
+/// spreadsheets and simple ML baselines.
+use super::TableState;
+use crate::defs;
+use crate::defs::{HackerID, NO_HACKER};
+
+/// Max operators in a game - every row has columns for this many, so rows from
+/// games with different operator counts line up.
+const MAX_OPERATORS: usize = 7;
+/// Max hackers in a backtrace list
+const MAX_BACKTRACE: usize = 13;
+
+impl TableState {
+    /// Named columns describing the entire table state. The same columns, in the same
+    /// order, are returned for every state. Absent hackers, operators, etc... are empty.
+    pub fn export_row(&self) -> Vec<(String, String)> {
+        let mut row = Vec::new();
+        let mut col = |name: String, value: String| row.push((name, value));
+
+        col("round".to_string(), self.round.to_string());
+        col(
+            "active_operator".to_string(),
+            self.active_operator.to_string(),
+        );
+        col(
+            "choice_state".to_string(),
+            format!("{:?}", self.choice_state),
+        );
+        col("facing".to_string(), hacker_cell(self.facing));
+        col("firewalls".to_string(), self.firewalls.to_string());
+        for (i, up) in self.databases.iter().enumerate() {
+            col(format!("database_{}", i), up.to_string());
+        }
+        for (i, up) in self.webservices.iter().enumerate() {
+            col(format!("webservice_{}", i), up.to_string());
+        }
+        for (name, deck) in [
+            ("hackers", &self.hackers),
+            ("breach", &self.breach),
+            ("discard", &self.discard),
+        ] {
+            col(format!("{}_count", name), deck.len().to_string());
+            for value in 1..=6 {
+                let count = deck
+                    .iter()
+                    .filter(|x| defs::hacker(x.hacker).value() == value)
+                    .count();
+                col(format!("{}_value_{}", name, value), count.to_string());
+            }
+        }
+
+        for i in 0..MAX_OPERATORS {
+            let operator = self.operators.get(i);
+            col(
+                format!("op{}_operator", i),
+                operator.map_or(String::new(), |x| format!("{:?}", x.operator)),
+            );
+            for slot in 0..3 {
+                col(
+                    format!("op{}_secure_{}", i, slot),
+                    operator.map_or(String::new(), |x| hacker_cell(x.secure_slots[slot])),
+                );
+            }
+            for idx in 0..MAX_BACKTRACE {
+                col(
+                    format!("op{}_backtrace_{}", i, idx),
+                    operator
+                        .and_then(|x| x.backtrace_list.get(idx))
+                        .map_or(String::new(), |x| hacker_cell(*x)),
+                );
+            }
+            col(
+                format!("op{}_burnout", i),
+                operator.map_or(String::new(), |x| x.burnout.to_string()),
+            );
+            col(
+                format!("op{}_desperation", i),
+                operator.map_or(String::new(), |x| x.desperation.to_string()),
+            );
+            col(
+                format!("op{}_idle", i),
+                operator.map_or(String::new(), |x| x.idle.to_string()),
+            );
+            col(
+                format!("op{}_no_secure", i),
+                operator.map_or(String::new(), |x| x.no_secure.to_string()),
+            );
+            col(
+                format!("op{}_no_give_assist", i),
+                operator.map_or(String::new(), |x| x.no_give_assist.to_string()),
+            );
+            col(
+                format!("op{}_no_talent", i),
+                operator.map_or(String::new(), |x| x.no_talent.to_string()),
+            );
+            col(
+                format!("op{}_skills", i),
+                operator.map_or(String::new(), |x| {
+                    x.skills
+                        .iter()
+                        .map(|x| format!("{:?}", x))
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                }),
+            );
+        }
+        row
+    }
+}
+
+/// Cell for a hacker - its ID, or empty if there is no hacker
+fn hacker_cell(hacker: HackerID) -> String {
+    if hacker == NO_HACKER {
+        String::new()
+    } else {
+        hacker.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Difficulty, GameConfig};
+    use super::*;
+    use crate::defs::OperatorType::*;
+    use arrayvec::ArrayVec;
+
+    fn state(operators: &[defs::OperatorType]) -> TableState {
+        let config = GameConfig::new(
+            Difficulty::Easy,
+            ArrayVec::from_iter(operators.iter().copied()),
+        );
+        TableState::setup_game(&config.unwrap())
+    }
+
+    fn cell<'a>(row: &'a [(String, String)], name: &str) -> &'a str {
+        &row.iter().find(|(x, _)| x == name).unwrap().1
+    }
+
+    #[test]
+    fn same_columns_for_any_operator_count() {
+        let small = state(&[Biggs]).export_row();
+        let large = state(&[Stone, Sniper, Rogue, Biggs, Rich, Charm, Admin]).export_row();
+        let names = |row: &Vec<(String, String)>| {
+            row.iter().map(|(x, _)| x.clone()).collect::<Vec<String>>()
+        };
+        assert_eq!(names(&small), names(&large));
+    }
+
+    #[test]
+    fn exports_values() {
+        let mut state = state(&[Biggs, Charm]);
+        state.operators[1].secure_slots[2] = 3;
+        state.operators[1].backtrace_list.push(13);
+        let row = state.export_row();
+
+        assert_eq!(cell(&row, "hackers_count"), "12");
+        assert_eq!(cell(&row, "facing"), "");
+        assert_eq!(cell(&row, "database_1"), "true");
+        assert_eq!(cell(&row, "op1_operator"), "Charm");
+        assert_eq!(cell(&row, "op1_secure_2"), "3");
+        assert_eq!(cell(&row, "op1_secure_0"), "");
+        assert_eq!(cell(&row, "op1_backtrace_0"), "13");
+        assert_eq!(cell(&row, "op1_skills"), "Charm");
+        assert_eq!(cell(&row, "op2_operator"), "");
+        assert_eq!(cell(&row, "op2_idle"), "");
+    }
+}
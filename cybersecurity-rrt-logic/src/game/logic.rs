@@ -1,26 +1,63 @@
 /// Actual logic to run a complete game
 use super::{GameConfig, TableState};
 use crate::defs;
-use crate::defs::{OperatorType, NO_HACKER};
+use crate::defs::OperatorType::*;
+use crate::defs::{HackerID, OperatorType, Penalty, Symbol, NO_HACKER};
 use crate::game::ChoiceState::ChooseAction;
 use crate::game::Difficulty::Easy;
 use crate::game::{
-    Choice, Difficulty, HackerCard, HackerDeck, OperatorID, OperatorState, TableEvent,
+    Bosses, BreachThreat, Choice, ChoiceCommand, ChoiceCommandError, ChoiceError, ChoiceState,
+    Difficulty, EventBatch, GameResult, HackerCard, HackerDeck, LossReason, OperatorID,
+    OperatorState, OperatorSummary, StreamSeeds, TableEvent, TurnSummary,
 };
 use arrayvec::ArrayVec;
 use rand::seq::{IteratorRandom, SliceRandom};
-use rand::Rng;
-use std::process::id;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use TableEvent::*;
 
+/// Most choices that can be valid at once (taking any hacker from the full backtrace lists
+/// of 7 operators with Biggs' flow)
+const MAX_CHOICES: usize = 7 * 13;
+
+type Choices = ArrayVec<Choice, MAX_CHOICES>;
+
+/// Operators each operator could assist, by operator count and then operator, as a bitmask
+/// of OperatorIDs
+static ASSIST_TARGETS: [[u8; 7]; 8] = assist_targets();
+
+/// Choices when facing a hacker, by its symbol (index in defs::SYMBOLS) and then whether
+/// the facing operator can secure it
+static FACE_CHOICES: [[&[Choice]; 2]; 4] = [
+    [&[Choice::Backtrace], &[Choice::Backtrace]],
+    [&[Choice::Backtrace], &[Choice::Secure, Choice::Backtrace]],
+    [&[Choice::Backtrace], &[Choice::Secure, Choice::Backtrace]],
+    [&[Choice::Backtrace], &[Choice::Secure, Choice::Backtrace]],
+];
+
+/// Builds ASSIST_TARGETS - everyone at the table but the operator itself
+const fn assist_targets() -> [[u8; 7]; 8] {
+    let mut table = [[0; 7]; 8];
+    let mut count = 0;
+    while count < 8 {
+        let mut operator = 0;
+        while operator < count {
+            table[count][operator] = ((1u16 << count) - 1) as u8 & !(1 << operator);
+            operator += 1;
+        }
+        count += 1;
+    }
+    table
+}
+
 // TODO: Convert to impl
 /// Gets (firewall mod, hacker_multiplier) depending on difficulty
-fn difficulty_mod(difficulty: &Difficulty) -> (usize, usize) {
+pub(super) fn difficulty_mod(difficulty: &Difficulty) -> (usize, usize) {
     match difficulty {
         Easy => (3, 6),
-        Normal => (2, 7),
-        Hard => (1, 7),
-        Heroic => (0, 7),
+        Difficulty::Normal => (2, 7),
+        Difficulty::Hard => (1, 7),
+        Difficulty::Heroic => (0, 7),
     }
 }
 
@@ -28,362 +65,4065 @@ fn init_operators(operators: &ArrayVec<OperatorType, 7>) -> ArrayVec<OperatorSta
     ArrayVec::from_iter(operators.iter().map(|x| OperatorState::new(x)))
 }
 
+/// Number of hackers the initial hacker deck can be chosen from
+pub(super) fn starting_hacker_pool() -> usize {
+    defs::HACKERS.iter().filter(|x| x.value() <= 4).count()
+}
+
 /// Shuffle initial hacker deck, with `hackers` number of hacker
 /// cards, chosen randomly without replacement from 1-4 value range
-fn shuffle(hackers: usize) -> HackerDeck {
+fn shuffle(hackers: usize, rng: &mut ChaCha8Rng) -> HackerDeck {
     // TODO: Is there a more efficient way?
-    let mut rng = rand::thread_rng();
     let mut valid_hackers: Vec<HackerCard> = defs::HACKERS
         .iter()
         .enumerate()
         .filter(|(_, x)| x.value() <= 4)
         .map(|(x, _)| HackerCard::new(x as u8))
         .collect();
-    valid_hackers.shuffle(&mut rng);
+    valid_hackers.shuffle(rng);
 
     return HackerDeck::from_iter(valid_hackers.iter().take(hackers).map(|x| *x));
 }
 
+/// Randomly pick `bosses` number of the value 6 hackers, without replacement
+fn pick_bosses(bosses: usize, rng: &mut ChaCha8Rng) -> Bosses {
+    let valid_bosses = defs::HACKERS
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| x.value() == 6)
+        .map(|(x, _)| x as HackerID);
+    Bosses::from_iter(valid_bosses.choose_multiple(rng, bosses))
+}
+
+/// Randomly pick `lieutenants` number of the value 5 hackers, without replacement
+fn pick_lieutenants(lieutenants: usize, rng: &mut ChaCha8Rng) -> Vec<HackerID> {
+    let valid_lieutenants = defs::HACKERS
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| x.value() == 5)
+        .map(|(x, _)| x as HackerID);
+    valid_lieutenants.choose_multiple(rng, lieutenants)
+}
+
 impl TableState {
     /// Returns a tablestate fully setup in accordance with
     /// the provided game config, ready for the first operator to perform their turn.
+    ///
+    /// The physical game has no decisions to make during setup - everyone starts
+    /// holding their own assist token with nothing secured, and who goes first is
+    /// decided by the order of operators in the GameConfig.
     pub fn setup_game(config: &GameConfig) -> TableState {
         let (firewall_mod, hacker_mult) = difficulty_mod(&config.difficulty);
+        let seeds = StreamSeeds::from_seed(config.seed.unwrap_or_else(|| rand::thread_rng().gen()));
+        let mut deck_rng = ChaCha8Rng::seed_from_u64(seeds.deck);
         TableState {
             firewalls: (config.operators.len() + firewall_mod) as u8,
+            max_firewalls: (config.operators.len() + firewall_mod) as u8,
             databases: [true; 3],
             webservices: [true; 6],
-            hackers: shuffle(config.operators.len() * hacker_mult),
+            hackers: shuffle(config.operators.len() * hacker_mult, &mut deck_rng),
             breach: HackerDeck::new(),
             discard: HackerDeck::new(),
+            bosses: match &config.bosses {
+                Some(bosses) => bosses.clone(),
+                None => pick_bosses(config.difficulty.boss_count(), &mut deck_rng),
+            },
             round: 0,
             facing: NO_HACKER,
             active_operator: 0,
             operators: init_operators(&config.operators),
             choice_state: ChooseAction(0),
+            seeds,
+            deck_rng,
+            revive_rng: ChaCha8Rng::seed_from_u64(seeds.revive),
+            options: config.options,
+            result: None,
+            coverage: None,
+            acting_again: false,
+            passed_to: None,
+            biggs_again: false,
+            decisions: 0,
+            turn_summary: TurnSummary::default(),
+            #[cfg(feature = "perf-assert")]
+            perf: Default::default(),
         }
     }
 
+    /// How the game ended, or None if it is still being played
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// Seeds of the random streams this game was set up with
+    pub fn seeds(&self) -> StreamSeeds {
+        self.seeds
+    }
+
     /// Returns the valid choices that can be performed based on current game state
     pub fn valid_choices(&self) -> Vec<Choice> {
+        self.valid_choices_iter().collect()
+    }
+
+    /// Same as valid_choices, but without allocating - for callers such as search which
+    /// enumerate choices in a tight loop.
+    pub fn valid_choices_iter(&self) -> impl Iterator<Item = Choice> {
+        let mut choices = Choices::new();
         match self.choice_state {
             ChooseAction(operator) => {
-                let mut choices = vec![Choice::Idle];
+                choices.push(Choice::Idle);
                 if !self.hackers.is_empty() {
                     choices.push(Choice::Face);
                 }
-                for i in 0..self.operators.len() {
-                    if i != operator as usize {
-                        choices.push(Choice::Assist(i as OperatorID));
+                let state = &self.operators[operator as usize];
+                if state.skills.contains(&state.operator) && !state.no_give_assist {
+                    let idle = self
+                        .operators
+                        .iter()
+                        .enumerate()
+                        .fold(0u8, |mask, (i, x)| mask | (x.idle as u8) << i);
+                    let mut targets =
+                        ASSIST_TARGETS[self.operators.len()][operator as usize] & !idle;
+                    while targets != 0 {
+                        choices.push(Choice::Assist(targets.trailing_zeros() as OperatorID));
+                        targets &= targets - 1;
+                    }
+                }
+                if self.can_repair(operator, Symbol::Webservice, &self.webservices) {
+                    choices.push(Choice::RepairWebservice);
+                }
+                if self.can_repair(operator, Symbol::Database, &self.databases) {
+                    choices.push(Choice::RepairDatabase);
+                }
+            }
+            ChoiceState::Reveal(_, _) => choices.push(Choice::Reveal),
+            ChoiceState::Face(operator) => {
+                let symbol = *defs::hacker(self.facing).symbol();
+                let state = &self.operators[operator as usize];
+                let securable = !state.no_secure
+                    && symbol
+                        .secure_slot()
+                        .is_some_and(|x| state.secure_slots[x] == NO_HACKER);
+                choices.extend(
+                    FACE_CHOICES[symbol as usize][securable as usize]
+                        .iter()
+                        .cloned(),
+                );
+            }
+            ChoiceState::Skill(operator) => {
+                for skill in self.applicable_skills(operator) {
+                    choices.push(Choice::UseSkill(skill));
+                }
+                choices.push(Choice::DeclineSkill);
+            }
+            ChoiceState::Flow(_)
+            | ChoiceState::RogueDesperationFlow
+            | ChoiceState::BiggsDesperationFlow => {
+                choices.push(Choice::UseFlow);
+                choices.push(Choice::DeclineFlow);
+            }
+            ChoiceState::CharmDesperationFlow => {
+                for (i, state) in self.operators.iter().enumerate() {
+                    if state.burnout {
+                        choices.push(Choice::RemoveBurnout(i as OperatorID));
+                    }
+                }
+            }
+            ChoiceState::StoneFlow => {
+                for (giver, target) in self.flow_assists() {
+                    choices.push(Choice::GiveAssist(giver, target));
+                }
+            }
+            ChoiceState::RogueFlow(_) => {
+                for (i, state) in self.operators.iter().enumerate() {
+                    if !state.backtrace_list.is_empty() {
+                        choices.push(Choice::DiscardBacktrace(i as OperatorID));
+                    }
+                }
+            }
+            ChoiceState::BiggsFlow => {
+                for (i, state) in self.operators.iter().enumerate() {
+                    for idx in 0..state.backtrace_list.len() {
+                        choices.push(Choice::TakeBacktrace(i as OperatorID, idx as u8));
+                    }
+                }
+            }
+            ChoiceState::BiggsFlowTarget(source) => {
+                for i in 0..self.operators.len() as OperatorID {
+                    if i != source {
+                        choices.push(Choice::PassTo(i));
+                    }
+                }
+            }
+            ChoiceState::RichFlow(count) => {
+                let top = &self.hackers[self.hackers.len() - count as usize..];
+                for ordering in orderings(count) {
+                    let order = ordering.iter().map(|x| top[top.len() - 1 - x].hacker);
+                    choices.push(Choice::OrderHackers(order.collect()));
+                }
+            }
+            ChoiceState::DiscardLeft(operator) => {
+                let state = &self.operators[operator as usize];
+                for (i, hacker) in state.secure_slots.iter().enumerate() {
+                    if *hacker != NO_HACKER {
+                        choices.push(Choice::DiscardSecure(i as u8));
+                    }
+                }
+            }
+            ChoiceState::Webservice(_, _) => {
+                for (i, up) in self.webservices.iter().enumerate() {
+                    if *up {
+                        choices.push(Choice::TakeDownWebservice(i as u8));
+                    }
+                }
+            }
+            ChoiceState::Pass(operator) => {
+                for neighbor in self.neighbors(operator) {
+                    choices.push(Choice::PassTo(neighbor));
+                }
+            }
+            ChoiceState::GameOver => {}
+        }
+        choices.into_iter()
+    }
+
+    /// Status of the indicated operator
+    pub fn operator_summary(&self, operator: OperatorID) -> OperatorSummary {
+        let state = &self.operators[operator as usize];
+        let backtrace_total = state.backtrace_total();
+        let track = state.track();
+        OperatorSummary {
+            operator: state.operator,
+            idle: state.idle,
+            burnout: state.burnout,
+            desperation: state.desperation,
+            backtrace_total,
+            track,
+            remaining_track: track.saturating_sub(backtrace_total),
+            flow_available: backtrace_total == track,
+            can_assist: state.skills.contains(&state.operator) && !state.no_give_assist,
+            no_secure: state.no_secure,
+            no_give_assist: state.no_give_assist,
+            no_talent: state.no_talent,
+            ignored_penalty_values: (1..=6)
+                .filter(|x| state.penalty_exemption(*x).is_some())
+                .collect(),
+            assists: state
+                .skills
+                .iter()
+                .filter(|x| **x != state.operator)
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Summary of the damage the end of the round would currently do, based only on
+    /// what operators can see: hackers revealed before they were breached, and secure
+    /// slots still empty.
+    pub fn breach_threat(&self) -> BreachThreat {
+        let mut threat = BreachThreat {
+            face_down: 0,
+            compromises: 0,
+            empty_slots: [0; 4],
+        };
+        for card in self.breach.iter() {
+            if !card.face_up {
+                threat.face_down += 1;
+            } else if defs::hacker(card.hacker).virus() {
+                threat.compromises += 1;
+            }
+        }
+        for state in self.operators.iter() {
+            for symbol in [Symbol::Keyboard, Symbol::Webservice, Symbol::Database] {
+                if state.secure_slots[symbol.secure_slot().unwrap()] == NO_HACKER {
+                    threat.empty_slots[symbol as usize] += 1;
+                }
+            }
+        }
+        threat
+    }
+
+    /// Choice to make on a player's behalf when they fail to decide in time, following
+    /// the default choice policy in the rule options. Always one of the valid choices.
+    /// None if there is no choice to make, because the game is over.
+    pub fn default_choice(&self) -> Option<Choice> {
+        let policy = self.options.default_choice;
+        let mut choices = self.valid_choices_iter();
+        match self.choice_state {
+            ChooseAction(_) => {
+                if policy.face && choices.any(|x| x == Choice::Face) {
+                    Some(Choice::Face)
+                } else {
+                    Some(Choice::Idle)
+                }
+            }
+            ChoiceState::Face(_) => {
+                if choices.any(|x| x == Choice::Secure) {
+                    Some(Choice::Secure)
+                } else {
+                    Some(Choice::Backtrace)
+                }
+            }
+            // applicable skills are listed before DeclineSkill
+            ChoiceState::Skill(_) if policy.use_skill => choices.next(),
+            ChoiceState::Skill(_) => Some(Choice::DeclineSkill),
+            ChoiceState::Flow(_)
+            | ChoiceState::RogueDesperationFlow
+            | ChoiceState::BiggsDesperationFlow
+                if policy.use_flow =>
+            {
+                Some(Choice::UseFlow)
+            }
+            ChoiceState::Flow(_)
+            | ChoiceState::RogueDesperationFlow
+            | ChoiceState::BiggsDesperationFlow => Some(Choice::DeclineFlow),
+            ChoiceState::Webservice(_, _)
+            | ChoiceState::Reveal(_, _)
+            | ChoiceState::CharmDesperationFlow
+            | ChoiceState::StoneFlow
+            | ChoiceState::RogueFlow(_)
+            | ChoiceState::BiggsFlow
+            | ChoiceState::BiggsFlowTarget(_)
+            | ChoiceState::RichFlow(_)
+            | ChoiceState::Pass(_) => choices.next(),
+            ChoiceState::DiscardLeft(operator) => {
+                let state = &self.operators[operator as usize];
+                let value = |x: &Choice| match x {
+                    Choice::DiscardSecure(slot) => {
+                        defs::hacker(state.secure_slots[*slot as usize]).value()
                     }
+                    _ => unreachable!("only secured hackers can be discarded"),
+                };
+                if policy.discard_highest {
+                    choices.max_by_key(value)
+                } else {
+                    choices.min_by_key(value)
                 }
-                choices
             }
-            _ => panic!("choice state not implemented"),
+            ChoiceState::GameOver => None,
         }
     }
 
     /// Perform the indicated action. TableState will be updated until next choice state is
     /// reached. Returns a vec consisting of events that occurred during the updates, in the
     /// order they happened.
-    pub fn choose(&self, choice: Choice) -> Vec<TableEvent> {
-        match choice {
-            _ => panic!("choice not implemented"),
+    ///
+    /// This only decides which events should happen - all mutation goes through `perform`,
+    /// so the returned events are a complete description of what changed.
+    ///
+    /// The events, and their order, depend only on the table state and the choice - never on
+    /// hash iteration order or anything else which varies between runs or platforms.
+    ///
+    /// Returns an error, leaving the TableState untouched, if the choice is not one of the
+    /// valid_choices.
+    pub fn choose(&mut self, choice: Choice) -> Result<Vec<TableEvent>, ChoiceError> {
+        #[cfg(feature = "perf-assert")]
+        let start = self.perf.start();
+        if !self.valid_choices_iter().any(|x| x == choice) {
+            return Result::Err(ChoiceError::InvalidChoice(choice));
         }
+        #[cfg(feature = "perf-assert")]
+        self.perf.validated(start.elapsed());
+        #[cfg(feature = "perf-assert")]
+        let made = choice.clone();
+        self.decisions += 1;
+        let events = self.resolve(choice);
+        #[cfg(feature = "perf-assert")]
+        self.perf.check(&made, start.elapsed());
+        Result::Ok(events)
     }
 
-    /// Update TableState corresponding with what the event says to do.
-    fn perform(&mut self, event: TableEvent) {
-        match event {
-            FirewallDelta(delta) => {
-                let result = (self.firewalls as i8) + delta;
-                if !(0..=3).contains(&result) {
-                    panic!(
-                        "delta out of range - firewalls must remain between 0..=3, cur {} delta {}",
-                        self.firewalls, delta
-                    );
-                }
-                self.firewalls = result as u8;
+    /// Resolves a valid choice, returning the events it produced
+    fn resolve(&mut self, choice: Choice) -> Vec<TableEvent> {
+        let mut events = Vec::new();
+        match choice {
+            Choice::Face => self.face(&mut events),
+            Choice::Reveal => match self.choice_state {
+                ChoiceState::Reveal(operator, skills) => self.reveal(operator, skills, &mut events),
+                _ => unreachable!("only valid when revealing a hacker"),
+            },
+            Choice::Assist(target) => {
+                self.emit(&mut events, Assist(target));
+                self.end_turn(&mut events);
             }
-            DatabaseRemove(idx) => {
-                if !(0..3).contains(&idx) {
-                    panic!("database index out of range, must be 0..=2, was {}", idx);
+            Choice::Idle => {
+                self.idle(self.active_operator, &mut events);
+                self.end_turn(&mut events);
+            }
+            Choice::Secure => {
+                self.emit(&mut events, Secure(self.deciding_operator()));
+                self.end_turn(&mut events);
+            }
+            Choice::Backtrace => {
+                let operator = self.deciding_operator();
+                let hacker = self.facing;
+                self.emit(&mut events, Backtrace(operator));
+                if self.check_loss(&mut events) {
+                    return events;
                 }
-                let idx = idx as usize;
-                if !self.databases[idx] {
-                    panic!("database index {} already removed", idx);
+                if !self.apply_penalty(operator, hacker, &mut events) {
+                    self.finish_backtrace(operator, &mut events);
                 }
-                self.databases[idx] = false;
             }
-            WebserviceRemove(idx) => {
-                if !(0..5).contains(&idx) {
-                    panic!("webservice index out of range, must be 0..=5, was {}", idx);
+            Choice::UseSkill(skill) => self.use_skill(skill, &mut events),
+            Choice::DeclineSkill => {
+                let operator = self.deciding_operator();
+                if self.facing == NO_HACKER {
+                    // declined to act again
+                    self.finish_turn(&mut events);
+                } else {
+                    self.emit(&mut events, ChoiceState(ChoiceState::Face(operator)));
                 }
-                let idx = idx as usize;
-                if !self.webservices[idx] {
-                    panic!("webservice index {} already removed", idx);
+            }
+            Choice::RepairWebservice => {
+                let repaired = self.webservices.iter().position(|x| !x);
+                self.repair(&mut events, Symbol::Webservice);
+                self.emit(&mut events, WebserviceRepair(repaired.unwrap() as u8));
+                self.end_turn(&mut events);
+            }
+            Choice::RepairDatabase => {
+                let repaired = self.databases.iter().position(|x| !x);
+                self.repair(&mut events, Symbol::Database);
+                self.emit(&mut events, DatabaseRepair(repaired.unwrap() as u8));
+                self.end_turn(&mut events);
+            }
+            Choice::TakeDownWebservice(idx) => {
+                let (operator, remaining) = match self.choice_state {
+                    ChoiceState::Webservice(operator, remaining) => (operator, remaining),
+                    _ => unreachable!("only valid when choosing a webservice"),
+                };
+                self.emit(&mut events, WebserviceRemove(idx));
+                if !self.penalty_compromise(operator, remaining, &mut events) {
+                    self.finish_backtrace(self.facing_operator(), &mut events);
                 }
-                self.webservices[idx] = false;
             }
-            Face => {
-                if self.facing != NO_HACKER {
-                    panic!("cannot face, already facing HackerID {}", self.facing);
+            Choice::UseFlow => match self.choice_state {
+                ChoiceState::RogueDesperationFlow => {
+                    self.emit(&mut events, ChoiceState(ChoiceState::RogueFlow(0)));
                 }
-                if self.hackers.is_empty() {}
-                match self.hackers.pop() {
-                    Some(x) => self.facing = x.hacker,
-                    None => panic!("cannot face, hacker deck is empty"),
+                ChoiceState::BiggsDesperationFlow => {
+                    self.emit(&mut events, ChoiceState(ChoiceState::BiggsFlow));
+                }
+                _ => {
+                    if !self.use_flow(self.deciding_operator(), &mut events) {
+                        self.end_turn(&mut events);
+                    }
                 }
+            },
+            Choice::DeclineFlow => self.end_turn(&mut events),
+            Choice::RemoveBurnout(target) => {
+                self.emit(&mut events, BurnoutRemoved(target));
+                self.end_turn(&mut events);
             }
-            Idle => {
-                if self.active_operator().idle {
-                    panic!(
-                        "cannot idle, operator {} already idle",
-                        self.active_operator
-                    );
+            Choice::GiveAssist(giver, target) => {
+                self.emit(&mut events, GiveAssist(giver, target));
+                self.end_turn(&mut events);
+            }
+            Choice::DiscardBacktrace(operator) => {
+                let uses = match self.choice_state {
+                    ChoiceState::RogueFlow(uses) => uses,
+                    _ => unreachable!("only valid when using Rogue's flow"),
+                };
+                self.emit(&mut events, DiscardBacktrace(operator));
+                if uses > 0 && self.backtraced_hackers() {
+                    self.emit(&mut events, ChoiceState(ChoiceState::RogueDesperationFlow));
+                } else {
+                    self.end_turn(&mut events);
                 }
-                self.active_operator().idle = true;
             }
-            ChoiceState(x) => {
-                self.choice_state = x;
+            Choice::TakeBacktrace(operator, idx) => {
+                self.emit(&mut events, TakeBacktrace(operator, idx));
+                self.emit(
+                    &mut events,
+                    ChoiceState(ChoiceState::BiggsFlowTarget(operator)),
+                );
+            }
+            Choice::OrderHackers(order) => {
+                self.emit(&mut events, OrderHackers(order));
+                self.end_turn(&mut events);
+            }
+            Choice::PassTo(target) => {
+                self.emit(&mut events, PassFacing(target));
+                self.emit(&mut events, ChoiceState(ChoiceState::Face(target)));
+            }
+            Choice::DiscardSecure(slot) => {
+                self.emit(&mut events, DiscardSecure(self.deciding_operator(), slot));
+                self.finish_backtrace(self.facing_operator(), &mut events);
             }
-            _ => panic!("event not implemented"),
         }
+        events
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::super::{Difficulty, GameConfig};
-    use super::*;
-    use crate::defs;
-    use crate::defs::{OperatorType, NO_HACKER};
-    use crate::game::{ChoiceState, OperatorID};
-    use arrayvec::ArrayVec;
-    use spectral::prelude::*;
-    use test_case::test_case;
 
-    static OPERATORS: [OperatorType; 7] = [
-        OperatorType::Stone,
-        OperatorType::Sniper,
-        OperatorType::Rogue,
-        OperatorType::Biggs,
-        OperatorType::Admin,
-        OperatorType::Charm,
-        OperatorType::Rich,
-    ];
+    /// Makes each of the choices in order, returning the events of each. Stops at the
+    /// first invalid choice, returning its index - the TableState is left as it was
+    /// after the choices before it.
+    pub fn apply_all(
+        &mut self,
+        choices: impl IntoIterator<Item = Choice>,
+    ) -> Result<Vec<EventBatch>, (usize, ChoiceError)> {
+        choices
+            .into_iter()
+            .enumerate()
+            .map(|(i, choice)| self.choose(choice).map_err(|err| (i, err)))
+            .collect()
+    }
 
-    fn get_operators(operators: usize) -> ArrayVec<OperatorType, 7> {
-        let chosen_operators = &OPERATORS[0..operators];
-        ArrayVec::from_iter(chosen_operators.iter().copied())
+    /// Returns the events choosing the indicated action would produce, without
+    /// modifying this TableState.
+    pub fn preview(&self, choice: Choice) -> Result<Vec<TableEvent>, ChoiceError> {
+        self.clone().choose(choice)
     }
 
-    /// Basic initial state with easy difficulty and 2 operators
-    fn initial_state(difficulty: Difficulty) -> TableState {
-        TableState::setup_game(&GameConfig::new(difficulty, get_operators(2)).unwrap())
+    /// Active operator draws the top hacker and must then decide where to place it,
+    /// first deciding whether to use a skill if any apply to it
+    fn face(&mut self, events: &mut Vec<TableEvent>) {
+        let operator = self.active_operator;
+        self.draw_facing(operator, true, events);
     }
 
-    fn initial_state_easy() -> TableState {
-        initial_state(Difficulty::Easy)
+    /// Operator draws the top hacker to face, turning it face up straight away unless
+    /// the reveal is delayed. They may use a skill on it unless skills is false.
+    fn draw_facing(&mut self, operator: OperatorID, skills: bool, events: &mut Vec<TableEvent>) {
+        self.emit(events, FaceStarted);
+        if self.options.delay_reveal {
+            self.emit(events, ChoiceState(ChoiceState::Reveal(operator, skills)));
+        } else {
+            self.reveal(operator, skills, events);
+        }
     }
 
-    #[test_case(1, Difficulty::Easy)]
-    #[test_case(2, Difficulty::Easy)]
-    #[test_case(3, Difficulty::Hard)]
-    #[test_case(4, Difficulty::Heroic)]
-    #[test_case(7, Difficulty::Heroic)]
-    #[test_case(7, Difficulty::Easy)]
-    #[test_case(7, Difficulty::Normal)]
-    fn sets_up_game(operators: usize, difficulty: Difficulty) {
-        let (firewall_mod, hacker_mult) = difficulty_mod(&difficulty);
-        let config = GameConfig::new(difficulty, get_operators(operators)).unwrap();
+    /// Operator turns the hacker they drew face up, then must decide where to place it,
+    /// first deciding whether to use a skill if any apply to it and skills is true
+    fn reveal(&mut self, operator: OperatorID, skills: bool, events: &mut Vec<TableEvent>) {
+        self.emit(events, FaceRevealed(self.facing));
+        if skills && self.applicable_skills(operator).next().is_some() {
+            self.emit(events, ChoiceState(ChoiceState::Skill(operator)));
+        } else {
+            self.emit(events, ChoiceState(ChoiceState::Face(operator)));
+        }
+    }
 
-        let state = TableState::setup_game(&config);
-        assert_eq!(
-            state.firewalls,
-            (operators + firewall_mod) as u8,
-            "firewalls = operators + 2"
-        );
-        assert_eq!(state.databases, [true; 3]);
-        assert_eq!(state.webservices, [true; 6]);
+    /// Skills held by the operator which they could use on the hacker they are facing.
+    /// None while a penalty prevents them from using skills.
+    fn applicable_skills(&self, operator: OperatorID) -> impl Iterator<Item = OperatorType> + '_ {
+        let state = &self.operators[operator as usize];
+        state
+            .skills
+            .iter()
+            .copied()
+            .filter(move |x| !state.no_talent && self.skill_applies(operator, *x))
+    }
 
-        assert_eq!(
-            state.hackers.len(),
-            operators * hacker_mult,
-            "hackers = operators * 7"
-        );
-        for hacker in state.hackers.iter() {
-            assert!(!hacker.face_up, "face down");
-            let hacker = defs::hacker(hacker.hacker);
-            assert!(hacker.value() <= 4, "not lieutenant or boss")
+    /// Whether the operator could use the indicated skill - on the hacker they are facing,
+    /// or to act again if they are not facing one. Passive skills never apply, as there
+    /// is no choice to make.
+    fn skill_applies(&self, operator: OperatorID, skill: OperatorType) -> bool {
+        let state = &self.operators[operator as usize];
+        if self.facing == NO_HACKER {
+            return skill == Rogue && !self.acting_again && !state.idle;
         }
+        let value = defs::hacker(self.facing).value();
+        match skill {
+            Stone => state
+                .backtrace_list
+                .iter()
+                .any(|x| defs::hacker(*x).value() == value),
+            Biggs | Charm => passes(skill, value) && self.neighbors(operator).next().is_some(),
+            // needs another hacker to face in its place
+            Rich => !self.hackers.is_empty(),
+            // passive, or only usable when not facing a hacker
+            Sniper | Rogue | Admin => false,
+        }
+    }
 
-        assert!(state.discard.is_empty());
-        assert!(state.breach.is_empty());
-        assert_eq!(state.round, 0);
-        assert_eq!(state.active_operator, 0);
-
-        assert_eq!(state.operators.len(), operators);
-        for (i, operator) in state.operators.iter().enumerate() {
-            assert_eq!(operator.secure_slots, [NO_HACKER; 3]);
-            assert!(operator.backtrace_list.is_empty());
-            assert_that(&operator.idle).is_false();
-            assert_eq!(operator.skills.len(), 1);
-            assert_eq!(operator.skills[0], OPERATORS[i]);
+    /// Operators seated next to the indicated operator, clockwise neighbor first. Only
+    /// one with two operators, and none when playing alone.
+    fn neighbors(&self, operator: OperatorID) -> impl Iterator<Item = OperatorID> {
+        let count = self.operators.len() as OperatorID;
+        let clockwise = (operator + 1) % count;
+        let counterclockwise = (operator + count - 1) % count;
+        let mut neighbors = ArrayVec::<OperatorID, 2>::new();
+        for x in [clockwise, counterclockwise] {
+            if x != operator && !neighbors.contains(&x) {
+                neighbors.push(x);
+            }
         }
+        neighbors.into_iter()
+    }
 
-        assert!(matches!(state.choice_state, ChooseAction(0)));
+    /// Operator uses the indicated skill on the hacker they are facing, continuing
+    /// until the next choice state is reached
+    fn use_skill(&mut self, skill: OperatorType, events: &mut Vec<TableEvent>) {
+        if let Some(coverage) = &mut self.coverage {
+            coverage.skill(skill);
+        }
+        match skill {
+            Stone => {
+                self.emit(events, DiscardFacing);
+                self.end_turn(events);
+            }
+            Rogue => {
+                self.emit(events, ActAgain);
+                let operator = self.active_operator;
+                self.emit(events, ChoiceState(ChooseAction(operator)));
+            }
+            Biggs | Charm => {
+                let operator = self.deciding_operator();
+                self.emit(events, ChoiceState(ChoiceState::Pass(operator)));
+            }
+            Rich => {
+                // the new hacker must be faced, with no further skills
+                let operator = self.deciding_operator();
+                self.emit(events, BottomDeckFacing);
+                self.draw_facing(operator, false, events);
+            }
+            Sniper | Admin => unreachable!("{:?} skill is passive and never offered", skill),
+        }
     }
 
-    #[test_case(1, false)]
-    #[test_case(1, true)]
-    #[test_case(7, true)]
-    #[test_case(7, false)]
-    fn valid_choice_choose_action(operators: usize, has_hackers: bool) {
-        let mut state =
-            TableState::setup_game(&GameConfig::new(Easy, get_operators(operators)).unwrap());
-        if !has_hackers {
-            state.hackers.clear();
+    /// Operator suffers the penalty of the hacker which was just added to their backtrace
+    /// list. Returns true if the penalty requires a decision, in which case the choice
+    /// state has been changed and finish_backtrace must be called once it is made, or if
+    /// the penalty lost the game.
+    fn apply_penalty(
+        &mut self,
+        operator: OperatorID,
+        hacker: HackerID,
+        events: &mut Vec<TableEvent>,
+    ) -> bool {
+        let count = self.operators.len() as OperatorID;
+        let hacker = defs::hacker(hacker);
+        let penalty = *hacker.penalty();
+        let exemption = match penalty {
+            Penalty::NoPenalty => None,
+            _ => self.operators[operator as usize].penalty_exemption(hacker.value()),
+        };
+        if let Some(skill) = exemption {
+            if let Some(coverage) = &mut self.coverage {
+                coverage.skill(skill);
+            }
+            self.emit(events, PenaltyIgnored(operator, skill));
+            return false;
         }
-        let choices = state.valid_choices();
-        let mut expected_choices = vec![Choice::Idle];
-        if has_hackers {
-            expected_choices.push(Choice::Face);
+        if let Some(coverage) = &mut self.coverage {
+            coverage.penalty(penalty);
         }
-        for i in 0..operators {
-            if i != state.active_operator as usize {
-                expected_choices.push(Choice::Assist(i as OperatorID));
+        match penalty {
+            Penalty::NoPenalty => false,
+            Penalty::DrawLeft => self.draw_penalty(operator, (operator + 1) % count, events),
+            Penalty::DrawRight => {
+                self.draw_penalty(operator, (operator + count - 1) % count, events)
+            }
+            Penalty::Compromise => self.penalty_compromise(operator, 1, events),
+            Penalty::DoubleCompromise => self.penalty_compromise(operator, 2, events),
+            Penalty::Ninja => {
+                self.ninja(operator, 1, events);
+                false
+            }
+            Penalty::DoubleNinja => {
+                self.ninja(operator, 2, events);
+                false
+            }
+            Penalty::NoSecure => {
+                self.emit(events, NoSecure(operator));
+                false
+            }
+            Penalty::NoGiveAssist => {
+                self.emit(events, NoGiveAssist(operator));
+                false
+            }
+            Penalty::Burnout => self.burnout(operator, events),
+            Penalty::NoGiveAssistAndBurnout => {
+                self.emit(events, NoGiveAssist(operator));
+                self.burnout(operator, events)
+            }
+            Penalty::NoTalentAndBurnout => {
+                self.emit(events, NoTalent(operator));
+                self.burnout(operator, events)
+            }
+            Penalty::DiscardSecure => {
+                let state = &self.operators[operator as usize];
+                if state.secure_slots == [NO_HACKER; 3] {
+                    self.emit(events, NothingToDiscard(operator));
+                    return false;
+                }
+                self.emit(events, ChoiceState(ChoiceState::DiscardLeft(operator)));
+                true
+            }
+            Penalty::NoSecureAndHackerRevive => {
+                self.emit(events, NoSecure(operator));
+                if !self.discard.is_empty() {
+                    let idx = self.revive_rng.gen_range(0..self.discard.len());
+                    let hacker = self.discard[idx].hacker;
+                    let position = self.revive_rng.gen_range(0..=self.hackers.len()) as u8;
+                    self.emit(events, HackerRevived(hacker, position));
+                }
+                false
+            }
+            Penalty::Idle => {
+                self.idle(operator, events);
+                false
             }
         }
-        assert_that(&choices.iter()).equals_iterator(&expected_choices.iter());
     }
 
-    #[test_case(2, 1, 3)]
-    #[test_case(1, - 1, 0)]
-    #[test_case(3, - 2, 1)]
-    #[test_case(3, - 3, 0)]
-    #[test_case(0, 3, 3)]
-    fn perform_firewall_delta_valid(initial: u8, delta: i8, expected: u8) {
-        let state = firewall_delta(initial, delta);
-        assert_that(&state.firewalls).is_equal_to(expected);
+    /// Operator idles for the remainder of the round, which frees them from any status
+    /// preventing them from acting. Nothing happens if they are already idle.
+    fn idle(&mut self, operator: OperatorID, events: &mut Vec<TableEvent>) {
+        let state = &self.operators[operator as usize];
+        if state.idle {
+            return;
+        }
+        if state.no_secure || state.no_give_assist || state.no_talent {
+            self.emit(events, ClearStatuses(operator));
+        }
+        self.emit(events, Idle(operator));
     }
 
-    #[test]
-    #[should_panic(
-        expected = "delta out of range - firewalls must remain between 0..=3, cur 0 delta -1"
-    )]
-    fn perform_firewall_delta_invalid() {
-        firewall_delta(0, -1);
+    /// Operator receives a burnout token. If they already have one they are overwhelmed
+    /// and the game is lost, in which case this returns true.
+    fn burnout(&mut self, operator: OperatorID, events: &mut Vec<TableEvent>) -> bool {
+        if self.operators[operator as usize].burnout {
+            let reason = LossReason::BurnedOut(operator);
+            self.emit(events, GameOver(GameResult::Loss(reason)));
+            return true;
+        }
+        self.emit(events, Burnout(operator));
+        false
     }
 
-    #[test]
-    #[should_panic(
-        expected = "delta out of range - firewalls must remain between 0..=3, cur 2 delta 2"
-    )]
-    fn perform_firewall_delta_invalid_2() {
-        firewall_delta(2, 2);
+    /// Resolves the indicated number of compromises caused by a penalty the operator
+    /// suffered. Each takes down a firewall, or a webservice of the operator's choice if
+    /// no firewalls are left. Returns true if the operator must choose a webservice.
+    fn penalty_compromise(
+        &mut self,
+        operator: OperatorID,
+        compromises: u8,
+        events: &mut Vec<TableEvent>,
+    ) -> bool {
+        for remaining in (0..compromises).rev() {
+            if self.firewalls > 0 {
+                self.emit(events, FirewallDelta(-1));
+            } else if self.webservices.iter().filter(|x| **x).count() > 1 {
+                self.emit(
+                    events,
+                    ChoiceState(ChoiceState::Webservice(operator, remaining)),
+                );
+                return true;
+            } else {
+                // no choice to make - takes down the last webservice, if any are left
+                self.take_down_webservice(events);
+            }
+        }
+        false
+    }
+
+    /// Places the indicated number of hackers from the top of the hacker stack onto the
+    /// breach, unseen, for a penalty the operator suffered. Stops early if the hacker
+    /// stack runs out, noting how many were short.
+    fn ninja(&mut self, operator: OperatorID, hackers: u8, events: &mut Vec<TableEvent>) {
+        for drawn in 0..hackers {
+            if self.hackers.is_empty() {
+                self.emit(events, ShortDraw(operator, hackers, drawn));
+                return;
+            }
+            self.emit(events, Breach);
+        }
+    }
+
+    /// Target operator draws the top hacker into their backtrace list and suffers its
+    /// penalty, for a penalty the operator suffered. Nothing is drawn if the hacker stack
+    /// is empty, which is noted instead.
+    fn draw_penalty(
+        &mut self,
+        operator: OperatorID,
+        target: OperatorID,
+        events: &mut Vec<TableEvent>,
+    ) -> bool {
+        match self.hackers.last() {
+            Some(card) => {
+                let hacker = card.hacker;
+                self.emit(events, DrawToBacktrace(target));
+                self.apply_penalty(target, hacker, events)
+            }
+            None => {
+                self.emit(events, ShortDraw(operator, 1, 0));
+                false
+            }
+        }
+    }
+
+    /// Indicated operator's backtrace and its penalty are fully resolved - they may use
+    /// their Flow if their backtrace list is now exactly their track, otherwise the turn
+    /// ends
+    fn finish_backtrace(&mut self, operator: OperatorID, events: &mut Vec<TableEvent>) {
+        if self.check_loss(events) {
+            return;
+        }
+        let state = &self.operators[operator as usize];
+        if state.backtrace_total() == state.track() {
+            self.emit(events, ChoiceState(ChoiceState::Flow(operator)));
+        } else {
+            self.end_turn(events);
+        }
+    }
+
+    /// Operator uses their own Flow, which is stronger in desperation mode. Returns true
+    /// if the flow requires a decision, in which case the choice state has been changed.
+    fn use_flow(&mut self, operator: OperatorID, events: &mut Vec<TableEvent>) -> bool {
+        let state = &self.operators[operator as usize];
+        let desperation = state.desperation;
+        if let Some(coverage) = &mut self.coverage {
+            coverage.flow(state.operator);
+        }
+        match state.operator {
+            Sniper => {
+                let count = if desperation { 3 } else { 2 };
+                let count = count.min(self.hackers.len()) as u8;
+                self.emit(events, DiscardHackers(count));
+            }
+            Admin => {
+                let count = if desperation { 3 } else { 2 };
+                let count = count.min(self.breach.len()) as u8;
+                self.emit(events, DiscardBreach(count));
+            }
+            Charm => {
+                if self.firewalls < self.max_firewalls {
+                    self.emit(events, FirewallDelta(1));
+                }
+                if desperation && self.operators.iter().any(|x| x.burnout) {
+                    self.emit(events, ChoiceState(ChoiceState::CharmDesperationFlow));
+                    return true;
+                }
+            }
+            Stone => {
+                if desperation && self.firewalls < self.max_firewalls {
+                    self.emit(events, FirewallDelta(1));
+                }
+                if self.flow_assists().next().is_some() {
+                    self.emit(events, ChoiceState(ChoiceState::StoneFlow));
+                    return true;
+                }
+            }
+            Rogue => {
+                // their own backtrace list always has a hacker to discard
+                let uses = if desperation { 1 } else { 0 };
+                self.emit(events, ChoiceState(ChoiceState::RogueFlow(uses)));
+                return true;
+            }
+            Biggs => {
+                // needs someone to give the hacker to
+                if self.operators.len() > 1 {
+                    if desperation {
+                        self.emit(events, BiggsAgain(true));
+                    }
+                    self.emit(events, ChoiceState(ChoiceState::BiggsFlow));
+                    return true;
+                }
+            }
+            Rich => {
+                let count = if desperation { 3 } else { 2 };
+                let count = count.min(self.hackers.len()) as u8;
+                if count > 0 {
+                    self.emit(events, RevealHackers(count));
+                }
+                if count > 1 {
+                    self.emit(events, ChoiceState(ChoiceState::RichFlow(count)));
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Operators who could give their assist token to another operator with Stone's
+    /// flow, paired with each operator they could give it to
+    fn flow_assists(&self) -> impl Iterator<Item = (OperatorID, OperatorID)> + '_ {
+        let count = self.operators.len() as OperatorID;
+        let givers = (0..count).filter(|x| {
+            let state = &self.operators[*x as usize];
+            state.skills.contains(&state.operator) && !state.no_give_assist
+        });
+        givers.flat_map(move |giver| {
+            (0..count)
+                .filter(move |x| *x != giver && !self.operators[*x as usize].idle)
+                .map(move |target| (giver, target))
+        })
+    }
+
+    /// Whether any operator has a hacker in their backtrace list
+    fn backtraced_hackers(&self) -> bool {
+        self.operators.iter().any(|x| !x.backtrace_list.is_empty())
+    }
+
+    /// Whether the variant allows the operator to repair infrastructure of the indicated
+    /// symbol - some of it must be down, and they must have a hacker of that symbol secured
+    fn can_repair(&self, operator: OperatorID, symbol: Symbol, infrastructure: &[bool]) -> bool {
+        let slot = symbol.secure_slot().unwrap();
+        self.options.repair
+            && infrastructure.contains(&false)
+            && self.operators[operator as usize].secure_slots[slot] != NO_HACKER
+    }
+
+    /// Deciding operator pays for a repair by discarding their secured hacker of the symbol
+    fn repair(&mut self, events: &mut Vec<TableEvent>, symbol: Symbol) {
+        let slot = symbol.secure_slot().unwrap() as u8;
+        self.emit(events, DiscardSecure(self.deciding_operator(), slot));
+    }
+
+    /// Operator facing the current hacker, or who faced the last one this turn - the active
+    /// operator unless it was passed to a neighbor
+    fn facing_operator(&self) -> OperatorID {
+        self.passed_to.unwrap_or(self.active_operator)
+    }
+
+    /// Operator who must make the current choice
+    fn deciding_operator(&self) -> OperatorID {
+        match self.choice_state {
+            ChoiceState::Flow(x)
+            | ChoiceState::Face(x)
+            | ChoiceState::Skill(x)
+            | ChoiceState::DiscardLeft(x)
+            | ChoiceState::Pass(x)
+            | ChoiceState::Webservice(x, _)
+            | ChoiceState::Reveal(x, _)
+            | ChooseAction(x) => x,
+            _ => self.active_operator,
+        }
+    }
+
+    /// Operator whose turn is next - the next operator clockwise from the active operator
+    /// who is not idle, which is the active operator again if everyone else is idle.
+    /// None if every operator is idle, meaning the round is over.
+    pub fn next_operator(&self) -> Option<OperatorID> {
+        let count = self.operators.len() as OperatorID;
+        (1..=count)
+            .map(|x| (self.active_operator + x) % count)
+            .find(|x| !self.operators[*x as usize].idle)
+    }
+
+    /// Why the game is lost, or None if it isn't
+    fn loss(&self) -> Option<LossReason> {
+        if !self.webservices.contains(&true) {
+            return Some(LossReason::Webservices);
+        }
+        if !self.databases.contains(&true) {
+            return Some(LossReason::Databases);
+        }
+        self.operators
+            .iter()
+            .position(|x| x.backtrace_total() > x.track())
+            .map(|x| LossReason::Overwhelmed(x as OperatorID))
+    }
+
+    /// Ends the game if it is lost, returning whether it was
+    fn check_loss(&mut self, events: &mut Vec<TableEvent>) -> bool {
+        match self.loss() {
+            Some(reason) => {
+                self.emit(events, GameOver(GameResult::Loss(reason)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Active operator's action is resolved - they may act again if they hold Rogue's
+    /// skill, otherwise their turn is over
+    fn end_turn(&mut self, events: &mut Vec<TableEvent>) {
+        if self.biggs_again && self.loss().is_none() {
+            self.emit(events, BiggsAgain(false));
+            if self.backtraced_hackers() {
+                self.emit(events, ChoiceState(ChoiceState::BiggsDesperationFlow));
+                return;
+            }
+        }
+        let operator = self.active_operator;
+        if self.loss().is_none() && self.applicable_skills(operator).any(|x| x == Rogue) {
+            self.emit(events, ChoiceState(ChoiceState::Skill(operator)));
+        } else {
+            self.finish_turn(events);
+        }
+    }
+
+    /// Summarizes the turn which just ended, then passes the turn clockwise to the next
+    /// operator who is not idle, ending the round if there is none. Ends the game instead
+    /// if it was lost during the turn.
+    fn finish_turn(&mut self, events: &mut Vec<TableEvent>) {
+        let summary = TurnSummary {
+            operator: self.active_operator,
+            ..self.turn_summary.clone()
+        };
+        self.emit(events, TurnSummary(summary));
+        self.next_turn(events);
+    }
+
+    /// Next operator who is not idle starts their turn, ending the round if there is none
+    fn next_turn(&mut self, events: &mut Vec<TableEvent>) {
+        if self.check_loss(events) {
+            return;
+        }
+        match self.next_operator() {
+            Some(next) => {
+                if next != self.active_operator {
+                    self.emit(events, ActiveOperator(next));
+                }
+                self.emit(events, ChoiceState(ChooseAction(next)));
+            }
+            None => self.end_round(events),
+        }
+    }
+
+    /// Every operator is idle - resolves the end of the round and starts the next one
+    fn end_round(&mut self, events: &mut Vec<TableEvent>) {
+        for operator in 0..self.operators.len() {
+            let slots = self.operators[operator].secure_slots;
+            if slots[Symbol::Keyboard.secure_slot().unwrap()] == NO_HACKER {
+                self.compromise(events);
+            }
+            if slots[Symbol::Webservice.secure_slot().unwrap()] == NO_HACKER {
+                self.take_down_webservice(events);
+            }
+            if slots[Symbol::Database.secure_slot().unwrap()] == NO_HACKER {
+                if let Some(idx) = self.databases.iter().position(|x| *x) {
+                    self.emit(events, DatabaseRemove(idx as u8));
+                }
+            }
+        }
+        let viruses = self
+            .breach
+            .iter()
+            .filter(|x| defs::hacker(x.hacker).virus())
+            .count();
+        for _ in 0..viruses {
+            self.compromise(events);
+        }
+        if self.check_loss(events) {
+            return;
+        }
+
+        for operator in 0..self.operators.len() {
+            let state = &self.operators[operator];
+            if state.burnout && !state.desperation {
+                self.emit(events, Desperation(operator as OperatorID));
+            }
+        }
+        self.emit(events, ReturnAssists);
+        for operator in 0..self.operators.len() {
+            let state = &self.operators[operator];
+            if !state.backtrace_list.is_empty() || state.secure_slots != [NO_HACKER; 3] {
+                self.emit(events, DiscardBoard(operator as OperatorID));
+            }
+        }
+
+        if self.round == 2 {
+            self.emit(events, GameOver(GameResult::Win));
+            return;
+        }
+        self.emit(events, RoundEnd);
+        self.rebuild_hackers(events);
+        self.next_turn(events);
+    }
+
+    /// Shuffles the breach back into the hacker stack for the round which just began,
+    /// along with one lieutenant per operator for the second round or the bosses for
+    /// the final round
+    fn rebuild_hackers(&mut self, events: &mut Vec<TableEvent>) {
+        if !self.breach.is_empty() {
+            self.emit(events, BreachToStack);
+        }
+        match self.round {
+            1 => self.emit(events, AddLieutenants(self.operators.len() as u8)),
+            2 => self.emit(events, AddBosses),
+            _ => panic!("no hackers to add in round {}", self.round),
+        }
+        self.emit(events, ShuffleHackers);
+    }
+
+    /// Takes down a firewall, or the first remaining webservice if no firewalls are left
+    fn compromise(&mut self, events: &mut Vec<TableEvent>) {
+        if self.firewalls > 0 {
+            self.emit(events, FirewallDelta(-1));
+        } else {
+            self.take_down_webservice(events);
+        }
+    }
+
+    /// Takes down the first remaining webservice, if any
+    fn take_down_webservice(&mut self, events: &mut Vec<TableEvent>) {
+        if let Some(idx) = self.webservices.iter().position(|x| *x) {
+            self.emit(events, WebserviceRemove(idx as u8));
+        }
+    }
+
+    /// Performs the event and records it in events, so later decisions
+    /// made while processing a choice see the updated state.
+    fn emit(&mut self, events: &mut Vec<TableEvent>, event: TableEvent) {
+        if let (Some(coverage), ChoiceState(x)) = (&mut self.coverage, &event) {
+            coverage.choice_state(x);
+        }
+        #[cfg(feature = "perf-assert")]
+        let start = std::time::Instant::now();
+        self.perform(event.clone());
+        #[cfg(feature = "perf-assert")]
+        self.perf.performed(&event, start.elapsed());
+        events.push(event);
+    }
+
+    /// Adds what the event does to the summary of the current turn, before it is performed
+    fn record_turn(&mut self, event: &TableEvent) {
+        let backtraced = match event {
+            Backtrace(_) => Some(self.facing),
+            DrawToBacktrace(_) => self.hackers.last().map(|x| x.hacker),
+            _ => None,
+        };
+        let summary = &mut self.turn_summary;
+        // invalid events are left for perform to reject
+        if let Some(hacker) = backtraced.filter(|x| *x != NO_HACKER) {
+            summary.backtraced.push(hacker);
+            let penalty = *defs::hacker(hacker).penalty();
+            if penalty != Penalty::NoPenalty {
+                summary.penalties.push(penalty);
+            }
+        }
+        match *event {
+            FaceRevealed(hacker) => summary.faced.push(hacker),
+            Secure(_) => summary.secured.push(self.facing),
+            FirewallDelta(delta) => summary.firewall_delta += delta,
+            WebserviceRemove(_) => summary.webservice_delta -= 1,
+            WebserviceRepair(_) => summary.webservice_delta += 1,
+            DatabaseRemove(_) => summary.database_delta -= 1,
+            DatabaseRepair(_) => summary.database_delta += 1,
+            // always right after the penalty it ignores was recorded
+            PenaltyIgnored(_, _) => {
+                summary.penalties.pop();
+            }
+            TurnSummary(_) | RoundEnd => *summary = TurnSummary::default(),
+            _ => {}
+        }
+    }
+
+    /// Update TableState corresponding with what the event says to do.
+    /// Panics if the event is not valid for the current state - choose should
+    /// never emit such an event.
+    pub(super) fn perform(&mut self, event: TableEvent) {
+        self.record_turn(&event);
+        match event {
+            FirewallDelta(delta) => {
+                let result = (self.firewalls as i8) + delta;
+                if !(0..=self.max_firewalls as i8).contains(&result) {
+                    panic!(
+                        "delta out of range - firewalls must remain between 0..={}, cur {} delta {}",
+                        self.max_firewalls, self.firewalls, delta
+                    );
+                }
+                self.firewalls = result as u8;
+            }
+            DatabaseRemove(idx) => {
+                if !(0..3).contains(&idx) {
+                    panic!("database index out of range, must be 0..=2, was {}", idx);
+                }
+                let idx = idx as usize;
+                if !self.databases[idx] {
+                    panic!("database index {} already removed", idx);
+                }
+                self.databases[idx] = false;
+            }
+            WebserviceRemove(idx) => {
+                if idx as usize >= self.webservices.len() {
+                    panic!("webservice index out of range, must be 0..=5, was {}", idx);
+                }
+                let idx = idx as usize;
+                if !self.webservices[idx] {
+                    panic!("webservice index {} already removed", idx);
+                }
+                self.webservices[idx] = false;
+            }
+            DatabaseRepair(idx) => {
+                let idx = idx as usize;
+                if idx >= self.databases.len() || self.databases[idx] {
+                    panic!("cannot repair database {}, not removed", idx);
+                }
+                self.databases[idx] = true;
+            }
+            WebserviceRepair(idx) => {
+                let idx = idx as usize;
+                if idx >= self.webservices.len() || self.webservices[idx] {
+                    panic!("cannot repair webservice {}, not removed", idx);
+                }
+                self.webservices[idx] = true;
+            }
+            FaceStarted => {
+                if self.facing != NO_HACKER {
+                    panic!("cannot face, already facing HackerID {}", self.facing);
+                }
+                if self.hackers.is_empty() {}
+                match self.hackers.pop() {
+                    Some(x) => self.facing = x.hacker,
+                    None => panic!("cannot face, hacker deck is empty"),
+                }
+                self.passed_to = None;
+            }
+            FaceRevealed(hacker) => {
+                if hacker != self.facing {
+                    panic!(
+                        "cannot reveal HackerID {}, facing HackerID {}",
+                        hacker, self.facing
+                    );
+                }
+            }
+            Idle(operator) => {
+                let state = &mut self.operators[operator as usize];
+                if state.idle {
+                    panic!("cannot idle, operator {} already idle", operator);
+                }
+                state.idle = true;
+            }
+            Assist(target) => {
+                if target as usize >= self.operators.len() {
+                    panic!("cannot assist, no operator {}", target);
+                }
+                if target == self.active_operator {
+                    panic!(
+                        "cannot assist, operator {} cannot assist themselves",
+                        target
+                    );
+                }
+                let giver = self.active_operator().operator;
+                let skills = &mut self.active_operator().skills;
+                match skills.iter().position(|x| *x == giver) {
+                    Some(idx) => skills.remove(idx),
+                    None => panic!(
+                        "cannot assist, operator {} no longer has their assist token",
+                        self.active_operator
+                    ),
+                };
+                self.operators[target as usize].skills.push(giver);
+            }
+            ActiveOperator(operator) => {
+                if operator as usize >= self.operators.len() {
+                    panic!("cannot make operator {} active, no such operator", operator);
+                }
+                self.active_operator = operator;
+            }
+            ChoiceState(x) => {
+                self.choice_state = x;
+            }
+            Secure(operator) => {
+                if self.facing == NO_HACKER {
+                    panic!("cannot secure, not facing a hacker");
+                }
+                let slot = match defs::hacker(self.facing).symbol().secure_slot() {
+                    Some(slot) => slot,
+                    None => panic!("cannot secure, HackerID {} has no symbol", self.facing),
+                };
+                let slots = &mut self.operators[operator as usize].secure_slots;
+                if slots[slot] != NO_HACKER {
+                    panic!(
+                        "cannot secure, operator {} slot {} already has HackerID {}",
+                        operator, slot, slots[slot]
+                    );
+                }
+                slots[slot] = self.facing;
+                self.facing = NO_HACKER;
+            }
+            ActAgain => {
+                if self.acting_again {
+                    panic!(
+                        "operator {} is already acting again this turn",
+                        self.active_operator
+                    );
+                }
+                self.acting_again = true;
+            }
+            DiscardFacing => {
+                if self.facing == NO_HACKER {
+                    panic!("cannot discard, not facing a hacker");
+                }
+                self.discard.push(HackerCard {
+                    hacker: self.facing,
+                    face_up: true,
+                });
+                self.facing = NO_HACKER;
+            }
+            BottomDeckFacing => {
+                if self.facing == NO_HACKER {
+                    panic!("cannot bottom-deck, not facing a hacker");
+                }
+                self.hackers.insert(0, HackerCard::new(self.facing));
+                self.facing = NO_HACKER;
+            }
+            PassFacing(operator) => {
+                if self.facing == NO_HACKER {
+                    panic!("cannot pass, not facing a hacker");
+                }
+                if operator as usize >= self.operators.len() {
+                    panic!("cannot pass to operator {}, no such operator", operator);
+                }
+                self.passed_to = Some(operator);
+            }
+            Backtrace(operator) => {
+                if self.facing == NO_HACKER {
+                    panic!("cannot backtrace, not facing a hacker");
+                }
+                let backtrace = &mut self.operators[operator as usize].backtrace_list;
+                if backtrace.is_full() {
+                    panic!(
+                        "cannot backtrace, operator {} backtrace list is full",
+                        operator
+                    );
+                }
+                backtrace.push(self.facing);
+                self.facing = NO_HACKER;
+            }
+            DrawToBacktrace(operator) => {
+                let backtrace = &mut self.operators[operator as usize].backtrace_list;
+                if backtrace.is_full() {
+                    panic!("cannot draw, operator {} backtrace list is full", operator);
+                }
+                match self.hackers.pop() {
+                    Some(x) => backtrace.push(x.hacker),
+                    None => panic!("cannot draw, hacker deck is empty"),
+                }
+            }
+            Breach => match self.hackers.pop() {
+                Some(x) if self.options.facedown_breach => {
+                    self.breach.push(HackerCard::new(x.hacker))
+                }
+                Some(x) => self.breach.push(x),
+                None => panic!("cannot breach, hacker deck is empty"),
+            },
+            NoSecure(operator) => self.operators[operator as usize].no_secure = true,
+            NoGiveAssist(operator) => self.operators[operator as usize].no_give_assist = true,
+            NoTalent(operator) => self.operators[operator as usize].no_talent = true,
+            ClearStatuses(operator) => {
+                let state = &mut self.operators[operator as usize];
+                state.no_secure = false;
+                state.no_give_assist = false;
+                state.no_talent = false;
+            }
+            Burnout(operator) => {
+                let state = &mut self.operators[operator as usize];
+                if state.burnout {
+                    panic!("operator {} already has a burnout token", operator);
+                }
+                state.burnout = true;
+            }
+            BurnoutRemoved(operator) => {
+                let state = &mut self.operators[operator as usize];
+                if !state.burnout {
+                    panic!("operator {} has no burnout token to remove", operator);
+                }
+                state.burnout = false;
+            }
+            HackerRevived(hacker, position) => {
+                let idx = match self.discard.iter().position(|x| x.hacker == hacker) {
+                    Some(x) => x,
+                    None => panic!(
+                        "cannot revive, HackerID {} is not in the discard pile",
+                        hacker
+                    ),
+                };
+                if position as usize > self.hackers.len() {
+                    panic!(
+                        "cannot revive to position {}, hacker stack only has {}",
+                        position,
+                        self.hackers.len()
+                    );
+                }
+                self.discard.remove(idx);
+                self.hackers
+                    .insert(position as usize, HackerCard::new(hacker));
+            }
+            PenaltyIgnored(operator, skill) => {
+                if !self.operators[operator as usize].skills.contains(&skill) {
+                    panic!("operator {} does not hold skill {:?}", operator, skill);
+                }
+            }
+            ShortDraw(operator, requested, drawn) => {
+                if !self.hackers.is_empty() {
+                    panic!(
+                        "operator {} has {} hackers left to draw",
+                        operator,
+                        self.hackers.len()
+                    );
+                }
+                if drawn >= requested {
+                    panic!(
+                        "cannot draw {} of {} hackers, nothing was short",
+                        drawn, requested
+                    );
+                }
+            }
+            NothingToDiscard(operator) => {
+                let state = &self.operators[operator as usize];
+                if let Some(slot) = state.secure_slots.iter().position(|x| *x != NO_HACKER) {
+                    panic!(
+                        "operator {} has a hacker to discard in slot {}",
+                        operator, slot
+                    );
+                }
+            }
+            DiscardHackers(count) => {
+                if count as usize > self.hackers.len() {
+                    panic!(
+                        "cannot discard {} hackers, hacker stack only has {}",
+                        count,
+                        self.hackers.len()
+                    );
+                }
+                for _ in 0..count {
+                    let hacker = self.hackers.pop().unwrap().hacker;
+                    self.discard.push(HackerCard {
+                        hacker,
+                        face_up: true,
+                    });
+                }
+            }
+            DiscardBreach(count) => {
+                if count as usize > self.breach.len() {
+                    panic!(
+                        "cannot discard {} hackers, breach only has {}",
+                        count,
+                        self.breach.len()
+                    );
+                }
+                for _ in 0..count {
+                    let hacker = self.breach.pop().unwrap().hacker;
+                    self.discard.push(HackerCard {
+                        hacker,
+                        face_up: true,
+                    });
+                }
+            }
+            GiveAssist(giver, target) => {
+                if giver as usize >= self.operators.len() || target as usize >= self.operators.len()
+                {
+                    panic!(
+                        "cannot give assist from {} to {}, no such operator",
+                        giver, target
+                    );
+                }
+                if giver == target {
+                    panic!(
+                        "cannot give assist, operator {} cannot assist themselves",
+                        giver
+                    );
+                }
+                let state = &mut self.operators[giver as usize];
+                let token = state.operator;
+                match state.skills.iter().position(|x| *x == token) {
+                    Some(idx) => state.skills.remove(idx),
+                    None => panic!(
+                        "cannot give assist, operator {} no longer has their assist token",
+                        giver
+                    ),
+                };
+                self.operators[target as usize].skills.push(token);
+            }
+            DiscardBacktrace(operator) => {
+                let backtrace = &mut self.operators[operator as usize].backtrace_list;
+                match backtrace.pop() {
+                    Some(hacker) => self.discard.push(HackerCard {
+                        hacker,
+                        face_up: true,
+                    }),
+                    None => panic!(
+                        "cannot discard, operator {} backtrace list is empty",
+                        operator
+                    ),
+                }
+            }
+            TakeBacktrace(operator, idx) => {
+                if self.facing != NO_HACKER {
+                    panic!("cannot take, already facing HackerID {}", self.facing);
+                }
+                let backtrace = &mut self.operators[operator as usize].backtrace_list;
+                if idx as usize >= backtrace.len() {
+                    panic!(
+                        "cannot take, operator {} backtrace list has no index {}",
+                        operator, idx
+                    );
+                }
+                self.facing = backtrace.remove(idx as usize);
+                self.passed_to = None;
+            }
+            BiggsAgain(again) => self.biggs_again = again,
+            RevealHackers(count) => {
+                let len = self.hackers.len();
+                if count as usize > len {
+                    panic!(
+                        "cannot reveal {} hackers, hacker stack only has {}",
+                        count, len
+                    );
+                }
+                for card in self.hackers[len - count as usize..].iter_mut() {
+                    card.face_up = true;
+                }
+            }
+            OrderHackers(order) => {
+                let len = self.hackers.len();
+                if order.len() > len {
+                    panic!(
+                        "cannot order {} hackers, hacker stack only has {}",
+                        order.len(),
+                        len
+                    );
+                }
+                let top = &mut self.hackers[len - order.len()..];
+                for card in top.iter() {
+                    if !card.face_up || !order.contains(&card.hacker) {
+                        panic!(
+                            "cannot order, HackerID {} on the hacker stack was not revealed",
+                            card.hacker
+                        );
+                    }
+                }
+                for (card, hacker) in top.iter_mut().rev().zip(order) {
+                    card.hacker = hacker;
+                }
+            }
+            Desperation(operator) => {
+                let state = &mut self.operators[operator as usize];
+                if !state.burnout || state.desperation {
+                    panic!(
+                        "operator {} cannot enter desperation, burnout {} desperation {}",
+                        operator, state.burnout, state.desperation
+                    );
+                }
+                state.desperation = true;
+            }
+            ReturnAssists => {
+                for state in self.operators.iter_mut() {
+                    state.skills = ArrayVec::from_iter([state.operator]);
+                }
+            }
+            DiscardBoard(operator) => {
+                let state = &mut self.operators[operator as usize];
+                let secured = state.secure_slots.iter().filter(|x| **x != NO_HACKER);
+                for hacker in secured.chain(state.backtrace_list.iter()) {
+                    self.discard.push(HackerCard {
+                        hacker: *hacker,
+                        face_up: true,
+                    });
+                }
+                state.secure_slots = [NO_HACKER; 3];
+                state.backtrace_list.clear();
+            }
+            RoundEnd => {
+                if self.round >= 2 {
+                    panic!("cannot end round {}, it is the last round", self.round);
+                }
+                if let Some(x) = self.operators.iter().position(|x| !x.idle) {
+                    panic!("cannot end round, operator {} is not idle", x);
+                }
+                self.round += 1;
+                for state in self.operators.iter_mut() {
+                    state.idle = false;
+                }
+            }
+            BreachToStack => {
+                for card in self.breach.drain(..) {
+                    self.hackers.push(HackerCard::new(card.hacker));
+                }
+            }
+            AddLieutenants(count) => {
+                for hacker in pick_lieutenants(count as usize, &mut self.deck_rng) {
+                    self.hackers.push(HackerCard::new(hacker));
+                }
+            }
+            AddBosses => {
+                for hacker in self.bosses.iter() {
+                    self.hackers.push(HackerCard::new(*hacker));
+                }
+            }
+            ShuffleHackers => {
+                for card in self.hackers.iter_mut() {
+                    card.face_up = false;
+                }
+                self.hackers.shuffle(&mut self.deck_rng);
+            }
+            TurnSummary(_) => {
+                self.acting_again = false;
+                self.passed_to = None;
+                self.biggs_again = false;
+            }
+            GameOver(result) => {
+                if let Some(x) = self.result {
+                    panic!("game already over with result {:?}", x);
+                }
+                self.result = Some(result);
+                self.choice_state = ChoiceState::GameOver;
+            }
+            DiscardSecure(operator, slot) => {
+                let slots = &mut self.operators[operator as usize].secure_slots;
+                let hacker = slots[slot as usize];
+                if hacker == NO_HACKER {
+                    panic!(
+                        "cannot discard, operator {} slot {} is empty",
+                        operator, slot
+                    );
+                }
+                slots[slot as usize] = NO_HACKER;
+                self.discard.push(HackerCard {
+                    hacker,
+                    face_up: true,
+                });
+            }
+        }
+    }
+}
+
+impl OperatorState {
+    /// total value of the hackers in the backtrace list
+    fn backtrace_total(&self) -> u8 {
+        self.backtrace_list
+            .iter()
+            .map(|x| defs::hacker(*x).value())
+            .sum()
+    }
+
+    /// total backtrace value the operator can handle before being overwhelmed
+    fn track(&self) -> u8 {
+        let operator = defs::operator(&self.operator);
+        if self.desperation {
+            operator.desperation_track()
+        } else {
+            operator.normal_track()
+        }
+    }
+
+    /// Skill the operator holds which lets them ignore the penalty of a hacker of the
+    /// indicated value, if any
+    fn penalty_exemption(&self, value: u8) -> Option<OperatorType> {
+        if self.no_talent {
+            return None;
+        }
+        self.skills
+            .iter()
+            .copied()
+            .find(|x| exempts_penalty(*x, value))
+    }
+}
+
+/// Whether the indicated skill is a passive one letting its holder ignore the penalty of
+/// hackers of the indicated value
+fn exempts_penalty(skill: OperatorType, value: u8) -> bool {
+    match skill {
+        Sniper => value.is_multiple_of(2),
+        Admin => !value.is_multiple_of(2),
+        Stone | Rogue | Biggs | Rich | Charm => false,
+    }
+}
+
+/// Every order the indicated number of hackers can be put in, as indices into them
+fn orderings(count: u8) -> &'static [&'static [usize]] {
+    match count {
+        2 => &[&[0, 1], &[1, 0]],
+        3 => &[
+            &[0, 1, 2],
+            &[0, 2, 1],
+            &[1, 0, 2],
+            &[1, 2, 0],
+            &[2, 0, 1],
+            &[2, 1, 0],
+        ],
+        _ => panic!("cannot order {} hackers", count),
+    }
+}
+
+/// Whether the indicated skill lets its holder pass hackers of the indicated value to a
+/// neighbor
+fn passes(skill: OperatorType, value: u8) -> bool {
+    match skill {
+        Biggs => !value.is_multiple_of(2),
+        Charm => value.is_multiple_of(2),
+        Stone | Sniper | Rogue | Rich | Admin => false,
+    }
+}
+
+impl ChoiceCommand {
+    /// Command for making the indicated choice in the table's current choice state.
+    pub fn new(state: &TableState, choice: Choice) -> ChoiceCommand {
+        ChoiceCommand {
+            choice_state: state.choice_state.clone(),
+            decision: state.decisions,
+            choice,
+        }
+    }
+
+    /// Makes the choice on the table, as long as it is still at the decision the
+    /// command was made for.
+    pub fn execute(&self, state: &mut TableState) -> Result<Vec<TableEvent>, ChoiceCommandError> {
+        if state.choice_state != self.choice_state || state.decisions != self.decision {
+            return Result::Err(ChoiceCommandError::StaleChoiceState);
+        }
+        state
+            .choose(self.choice.clone())
+            .map_err(ChoiceCommandError::Choice)
+    }
+
+    /// Returns the events executing would produce, without modifying the table.
+    pub fn dry_run(&self, state: &TableState) -> Result<Vec<TableEvent>, ChoiceCommandError> {
+        self.execute(&mut state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Difficulty, GameConfig};
+    use super::*;
+    use crate::defs;
+    use crate::defs::{OperatorType, NO_HACKER, OPERATOR_TYPES};
+    use crate::game::{ChoiceState, DefaultChoicePolicy, OperatorID, RuleOptions};
+    use arrayvec::ArrayVec;
+    use spectral::prelude::*;
+    use test_case::test_case;
+
+    static OPERATORS: [OperatorType; 7] = [
+        OperatorType::Stone,
+        OperatorType::Sniper,
+        OperatorType::Rogue,
+        OperatorType::Biggs,
+        OperatorType::Admin,
+        OperatorType::Charm,
+        OperatorType::Rich,
+    ];
+
+    fn get_operators(operators: usize) -> ArrayVec<OperatorType, 7> {
+        let chosen_operators = &OPERATORS[0..operators];
+        ArrayVec::from_iter(chosen_operators.iter().copied())
+    }
+
+    /// Basic initial state with easy difficulty and 2 operators
+    fn initial_state(difficulty: Difficulty) -> TableState {
+        TableState::setup_game(&GameConfig::new(difficulty, get_operators(2)).unwrap())
+    }
+
+    fn initial_state_easy() -> TableState {
+        initial_state(Difficulty::Easy)
+    }
+
+    #[test_case(1, Difficulty::Easy)]
+    #[test_case(2, Difficulty::Easy)]
+    #[test_case(3, Difficulty::Hard)]
+    #[test_case(4, Difficulty::Heroic)]
+    #[test_case(7, Difficulty::Heroic)]
+    #[test_case(7, Difficulty::Easy)]
+    #[test_case(7, Difficulty::Normal)]
+    fn sets_up_game(operators: usize, difficulty: Difficulty) {
+        let (firewall_mod, hacker_mult) = difficulty_mod(&difficulty);
+        let config = GameConfig::new(difficulty, get_operators(operators)).unwrap();
+
+        let state = TableState::setup_game(&config);
+        assert_eq!(
+            state.firewalls,
+            (operators + firewall_mod) as u8,
+            "firewalls = operators + 2"
+        );
+        assert_eq!(state.databases, [true; 3]);
+        assert_eq!(state.webservices, [true; 6]);
+
+        assert_eq!(
+            state.hackers.len(),
+            operators * hacker_mult,
+            "hackers = operators * 7"
+        );
+        for hacker in state.hackers.iter() {
+            assert!(!hacker.face_up, "face down");
+            let hacker = defs::hacker(hacker.hacker);
+            assert!(hacker.value() <= 4, "not lieutenant or boss")
+        }
+
+        assert!(state.discard.is_empty());
+        assert!(state.breach.is_empty());
+        assert_eq!(state.bosses.len(), config.difficulty.boss_count());
+        for (i, boss) in state.bosses.iter().enumerate() {
+            assert_eq!(defs::hacker(*boss).value(), 6, "boss");
+            assert!(!state.bosses[..i].contains(boss), "unique boss");
+        }
+        assert_eq!(state.round, 0);
+        assert_eq!(state.active_operator, 0);
+
+        assert_eq!(state.operators.len(), operators);
+        for (i, operator) in state.operators.iter().enumerate() {
+            assert_eq!(operator.secure_slots, [NO_HACKER; 3]);
+            assert!(operator.backtrace_list.is_empty());
+            assert_that(&operator.idle).is_false();
+            assert_eq!(operator.skills.len(), 1);
+            assert_eq!(operator.skills[0], OPERATORS[i]);
+        }
+
+        assert!(matches!(state.choice_state, ChooseAction(0)));
+    }
+
+    #[test]
+    fn sets_up_chosen_bosses() {
+        let config = GameConfig::new(Difficulty::Heroic, get_operators(2))
+            .unwrap()
+            .with_bosses(ArrayVec::from_iter([60, 64]))
+            .unwrap();
+        let state = TableState::setup_game(&config);
+        assert_that(&state.bosses.to_vec()).is_equal_to(vec![60, 64]);
+    }
+
+    #[test]
+    fn sets_up_seeded_game() {
+        let config = || GameConfig::new(Difficulty::Heroic, get_operators(3)).unwrap();
+        let state = TableState::setup_game(&config().with_seed(42));
+        let same = TableState::setup_game(&config().with_seed(42));
+        let other = TableState::setup_game(&config().with_seed(43));
+
+        assert_that(&state.seeds()).is_equal_to(StreamSeeds::from_seed(42));
+        assert_that(&state.hackers.to_vec()).is_equal_to(same.hackers.to_vec());
+        assert_that(&state.bosses.to_vec()).is_equal_to(same.bosses.to_vec());
+        assert_that(&state.hackers.to_vec()).is_not_equal_to(other.hackers.to_vec());
+
+        let seeds = state.seeds();
+        assert_ne!(seeds.deck, seeds.revive);
+        assert_ne!(seeds.deck, seeds.bot);
+        assert_ne!(seeds.revive, seeds.bot);
+    }
+
+    /// Golden values for a seeded game, which must be identical on every platform. If
+    /// these change, the rules changed and RULES_VERSION must be bumped.
+    #[test]
+    fn golden_seeded_game() {
+        let config = GameConfig::new(Difficulty::Normal, get_operators(3)).unwrap();
+        let mut state = TableState::setup_game(&config.with_seed(2022));
+        assert_that(
+            &state
+                .hackers
+                .iter()
+                .map(|x| x.hacker)
+                .collect::<Vec<HackerID>>(),
+        )
+        .is_equal_to(vec![
+            3, 28, 27, 10, 49, 19, 37, 36, 8, 50, 39, 0, 1, 44, 2, 40, 15, 12, 11, 35, 22,
+        ]);
+        assert_that(&state.bosses.to_vec()).is_equal_to(vec![60]);
+
+        let events = state.apply_all([Choice::Assist(2), Choice::Face]).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            vec![
+                Assist(2),
+                TurnSummary(summary(0)),
+                ActiveOperator(1),
+                ChoiceState(ChooseAction(1)),
+            ],
+            vec![
+                FaceStarted,
+                FaceRevealed(22),
+                ChoiceState(ChoiceState::Face(1)),
+            ],
+        ]);
+        assert_that(&state.facing).is_equal_to(22);
+    }
+
+    #[test]
+    fn assist_targets_exclude_self() {
+        assert_that(&ASSIST_TARGETS[1][0]).is_equal_to(0);
+        assert_that(&ASSIST_TARGETS[3]).is_equal_to([0b110, 0b101, 0b011, 0, 0, 0, 0]);
+        assert_that(&ASSIST_TARGETS[7][6]).is_equal_to(0b0111111);
+    }
+
+    #[test_case(1, false)]
+    #[test_case(1, true)]
+    #[test_case(7, true)]
+    #[test_case(7, false)]
+    fn valid_choice_choose_action(operators: usize, has_hackers: bool) {
+        let mut state =
+            TableState::setup_game(&GameConfig::new(Easy, get_operators(operators)).unwrap());
+        if !has_hackers {
+            state.hackers.clear();
+        }
+        let choices = state.valid_choices();
+        let mut expected_choices = vec![Choice::Idle];
+        if has_hackers {
+            expected_choices.push(Choice::Face);
+        }
+        for i in 0..operators {
+            if i != state.active_operator as usize {
+                expected_choices.push(Choice::Assist(i as OperatorID));
+            }
+        }
+        assert_that(&choices.iter()).equals_iterator(&expected_choices.iter());
+        assert_that(&state.valid_choices_iter().collect::<Vec<Choice>>())
+            .is_equal_to(&expected_choices);
+    }
+
+    #[test]
+    fn default_choice_choose_action() {
+        let state = initial_state_easy();
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::Idle));
+        assert_that(&state.valid_choices()).contains(Choice::Idle);
+    }
+
+    #[test]
+    fn default_choice_game_over() {
+        let mut state = initial_state_easy();
+        state.choice_state = ChoiceState::GameOver;
+        assert_that(&state.default_choice()).is_none();
+    }
+
+    #[test]
+    fn default_choice_policy() {
+        let policy = DefaultChoicePolicy {
+            face: true,
+            use_skill: true,
+            use_flow: true,
+            discard_highest: true,
+        };
+        let mut state = initial_state_easy();
+        state.options.default_choice = policy;
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::Face));
+        state.choice_state = ChoiceState::Flow(0);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::UseFlow));
+
+        // 21 is value 2, 3 is value 1
+        let mut state = discard_state([21, NO_HACKER, 3]);
+        state.options.default_choice = policy;
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::DiscardSecure(0)));
+    }
+
+    #[test]
+    fn operator_summary() {
+        let mut state = initial_state_easy();
+        state.perform(Assist(1));
+        // values 4, 3 and 2
+        state.operators[1].backtrace_list = ArrayVec::from_iter([40, 26, 13]);
+        assert_that(&state.operator_summary(1)).is_equal_to(OperatorSummary {
+            operator: OperatorType::Sniper,
+            idle: false,
+            burnout: false,
+            desperation: false,
+            backtrace_total: 9,
+            track: 9,
+            remaining_track: 0,
+            flow_available: true,
+            can_assist: true,
+            no_secure: false,
+            no_give_assist: false,
+            no_talent: false,
+            ignored_penalty_values: ArrayVec::from_iter([2, 4, 6]),
+            assists: ArrayVec::from_iter([OperatorType::Stone]),
+        });
+
+        state.operators[1].desperation = true;
+        let summary = state.operator_summary(1);
+        assert_that(&summary.track).is_equal_to(12);
+        assert_that(&summary.remaining_track).is_equal_to(3);
+        assert_that(&summary.flow_available).is_false();
+        assert_that(&state.operator_summary(0).can_assist).is_false();
+    }
+
+    #[test]
+    fn breach_threat() {
+        let mut state = initial_state_easy();
+        state.operators[0].secure_slots = [5, NO_HACKER, 7];
+        assert_that(&state.breach_threat()).is_equal_to(BreachThreat {
+            face_down: 0,
+            compromises: 0,
+            empty_slots: [0, 1, 2, 1],
+        });
+    }
+
+    #[test_case(2, 0, 1, 0; "unseen")]
+    #[test_case(2, 1, 0, 1; "revealed virus")]
+    #[test_case(60, 1, 1, 1; "double ninja past the revealed hacker")]
+    fn breach_threat_ninja(hacker: HackerID, revealed: u8, face_down: u8, compromises: u8) {
+        let mut state = facing_state(hacker);
+        // 9 on top is a virus, 16 is not
+        state.hackers = HackerDeck::from_iter([16, 9].map(HackerCard::new));
+        if revealed > 0 {
+            state.perform(RevealHackers(revealed));
+        }
+        state.choose(Choice::Backtrace).unwrap();
+        assert_that(&state.breach_threat()).is_equal_to(BreachThreat {
+            face_down,
+            compromises,
+            empty_slots: [0, 2, 2, 2],
+        });
+    }
+
+    #[test]
+    fn breach_threat_facedown_breach() {
+        let mut state = facing_state(2);
+        state.options.facedown_breach = true;
+        state.hackers = HackerDeck::from_iter([16, 9].map(HackerCard::new));
+        state.perform(RevealHackers(1));
+        state.choose(Choice::Backtrace).unwrap();
+        assert_that(&state.breach[0]).is_equal_to(HackerCard::new(9));
+        assert_that(&state.breach_threat()).is_equal_to(BreachThreat {
+            face_down: 1,
+            compromises: 0,
+            empty_slots: [0, 2, 2, 2],
+        });
+    }
+
+    #[test_case(2, 1, 3)]
+    #[test_case(1, - 1, 0)]
+    #[test_case(3, - 2, 1)]
+    #[test_case(3, - 3, 0)]
+    #[test_case(0, 3, 3)]
+    fn perform_firewall_delta_valid(initial: u8, delta: i8, expected: u8) {
+        let state = firewall_delta(initial, delta);
+        assert_that(&state.firewalls).is_equal_to(expected);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "delta out of range - firewalls must remain between 0..=3, cur 0 delta -1"
+    )]
+    fn perform_firewall_delta_invalid() {
+        firewall_delta(0, -1);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "delta out of range - firewalls must remain between 0..=3, cur 2 delta 2"
+    )]
+    fn perform_firewall_delta_invalid_2() {
+        firewall_delta(2, 2);
+    }
+
+    fn firewall_delta(initial: u8, delta: i8) -> TableState {
+        let mut state = initial_state_easy();
+        state.max_firewalls = 3;
+        state.firewalls = initial;
+        state.perform(FirewallDelta(delta));
+        state
+    }
+
+    #[test_case([false, true, false], 1, [false, false, false])]
+    #[test_case([true, true, false], 0, [false, true, false])]
+    #[test_case([true, true, true], 2, [true, true, false])]
+    fn perform_database_remove_valid(initial: [bool; 3], delta: u8, expected: [bool; 3]) {
+        let state = database_remove(initial, delta);
+        assert_that(&state.databases).is_equal_to(&expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "database index out of range, must be 0..=2, was 3")]
+    fn perform_database_remove_invalid() {
+        database_remove([true, false, true], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "database index 1 already removed")]
+    fn perform_database_remove_invalid_2() {
+        database_remove([true, false, true], 1);
+    }
+
+    fn database_remove(initial: [bool; 3], idx: u8) -> TableState {
+        let mut state = initial_state_easy();
+        state.databases = initial;
+        state.perform(DatabaseRemove(idx));
+        state
+    }
+
+    #[test_case([false, true, false, false, false, false], 1, [false, false, false, false, false, false])]
+    #[test_case([true, true, false, false, false, false], 0, [false, true, false, false, false, false])]
+    #[test_case([true, true, true, false, false, false], 2, [true, true, false, false, false, false])]
+    fn perform_webservice_remove_valid(initial: [bool; 6], delta: u8, expected: [bool; 6]) {
+        let state = webservice_remove(initial, delta);
+        assert_that(&state.webservices).is_equal_to(&expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "webservice index out of range, must be 0..=5, was 6")]
+    fn perform_webservice_remove_invalid() {
+        webservice_remove([true, false, true, false, false, false], 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "webservice index 1 already removed")]
+    fn perform_webservice_remove_invalid_2() {
+        webservice_remove([true, false, true, false, false, false], 1);
+    }
+
+    fn webservice_remove(initial: [bool; 6], idx: u8) -> TableState {
+        let mut state = initial_state_easy();
+        state.webservices = initial;
+        state.perform(WebserviceRemove(idx));
+        state
+    }
+
+    #[test]
+    fn perform_face() {
+        let mut state = initial_state_easy();
+        let mut expected_hackers = state.hackers.clone();
+        let expected_face = expected_hackers.pop().unwrap();
+        state.perform(FaceStarted);
+        assert_that(&state.hackers.iter()).equals_iterator(&expected_hackers.iter());
+        assert_that(&state.facing).is_equal_to(&expected_face.hacker);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot face, hacker deck is empty")]
+    fn perform_face_invalid_deck() {
+        let mut state = initial_state_easy();
+        state.hackers.clear();
+        state.perform(FaceStarted);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot face, already facing HackerID 3")]
+    fn perform_face_already_facing() {
+        let mut state = initial_state_easy();
+        state.facing = 3;
+        state.perform(FaceStarted);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot reveal HackerID 3, facing HackerID 66")]
+    fn perform_face_revealed_wrong_hacker() {
+        let mut state = initial_state_easy();
+        state.perform(FaceRevealed(3));
+    }
+
+    #[test]
+    fn perform_idle() {
+        let mut state = initial_state_easy();
+        state.perform(Idle(0));
+        assert_that(&state.operators[0].idle).is_true();
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot idle, operator 0 already idle")]
+    fn perform_idle_invalid() {
+        let mut state = initial_state_easy();
+        state.operators[0].idle = true;
+        state.perform(Idle(0));
+    }
+
+    #[test]
+    fn perform_assist() {
+        let mut state = initial_state_easy();
+        state.perform(Assist(1));
+        assert_that(&state.operators[0].skills.is_empty()).is_true();
+        assert_that(&state.operators[1].skills.iter())
+            .equals_iterator(&[OPERATORS[1], OPERATORS[0]].iter());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot assist, operator 0 no longer has their assist token")]
+    fn perform_assist_twice() {
+        let mut state = initial_state_easy();
+        state.perform(Assist(1));
+        state.perform(Assist(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot assist, operator 0 cannot assist themselves")]
+    fn perform_assist_self() {
+        let mut state = initial_state_easy();
+        state.perform(Assist(0));
+    }
+
+    #[test]
+    fn perform_active_operator() {
+        let mut state = initial_state_easy();
+        state.perform(ActiveOperator(1));
+        assert_that(&state.active_operator).is_equal_to(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot make operator 2 active, no such operator")]
+    fn perform_active_operator_invalid() {
+        let mut state = initial_state_easy();
+        state.perform(ActiveOperator(2));
+    }
+
+    #[test]
+    fn command_stale_choice_state() {
+        let mut state = initial_state_easy();
+        let command = ChoiceCommand::new(&state, Choice::Idle);
+        state.perform(ChoiceState(ChoiceState::Face(0)));
+        assert!(matches!(
+            command.dry_run(&state),
+            Err(ChoiceCommandError::StaleChoiceState)
+        ));
+        assert!(matches!(
+            command.execute(&mut state),
+            Err(ChoiceCommandError::StaleChoiceState)
+        ));
+        assert_that(&state.choice_state).is_equal_to(ChoiceState::Face(0));
+    }
+
+    #[test]
+    fn command_stale_decision() {
+        let mut state = initial_state_easy();
+        state.operators[1].idle = true;
+        let command = ChoiceCommand::new(&state, Choice::Assist(1));
+        state.choose(Choice::Face).unwrap();
+        state.choose(Choice::Backtrace).unwrap();
+        // operator 1 is idle, so it is operator 0's turn again
+        assert_that(&state.choice_state).is_equal_to(ChooseAction(0));
+        assert_that(&command.execute(&mut state))
+            .is_equal_to(Err(ChoiceCommandError::StaleChoiceState));
+    }
+
+    #[test]
+    fn choose_face() {
+        let mut state = initial_state_easy();
+        let mut expected_hackers = state.hackers.clone();
+        let expected_face = expected_hackers.pop().unwrap();
+
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            FaceStarted,
+            FaceRevealed(expected_face.hacker),
+            ChoiceState(ChoiceState::Face(0)),
+        ]);
+        assert_that(&state.facing).is_equal_to(expected_face.hacker);
+        assert_that(&state.hackers.iter()).equals_iterator(&expected_hackers.iter());
+        assert_that(&state.choice_state).is_equal_to(ChoiceState::Face(0));
+    }
+
+    #[test]
+    fn choose_face_delay_reveal() {
+        let config = GameConfig::new(Easy, get_operators(2))
+            .unwrap()
+            .with_options(RuleOptions {
+                delay_reveal: true,
+                ..RuleOptions::default()
+            });
+        let mut state = TableState::setup_game(&config);
+        let expected_face = state.hackers.last().unwrap().hacker;
+
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events)
+            .is_equal_to(vec![FaceStarted, ChoiceState(ChoiceState::Reveal(0, true))]);
+        assert_that(&state.facing).is_equal_to(expected_face);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::Reveal]);
+
+        let events = state.choose(Choice::Reveal).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            FaceRevealed(expected_face),
+            ChoiceState(ChoiceState::Face(0)),
+        ]);
+    }
+
+    #[test]
+    fn choose_face_empty_deck() {
+        let mut state = initial_state_easy();
+        state.hackers.clear();
+        assert_that(&state.choose(Choice::Face))
+            .is_equal_to(Err(ChoiceError::InvalidChoice(Choice::Face)));
+        assert_that(&state.choice_state).is_equal_to(ChooseAction(0));
+    }
+
+    #[test]
+    fn choose_assist() {
+        let mut state = TableState::setup_game(&GameConfig::new(Easy, get_operators(3)).unwrap());
+        let events = state.choose(Choice::Assist(2)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Assist(2),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].skills.is_empty()).is_true();
+        assert_that(&state.operators[2].skills.to_vec())
+            .is_equal_to(vec![OPERATORS[2], OPERATORS[0]]);
+        assert_that(&state.active_operator).is_equal_to(1);
+    }
+
+    #[test]
+    fn choose_assist_wraps_around() {
+        let mut state = initial_state_easy();
+        state.active_operator = 1;
+        state.choice_state = ChooseAction(1);
+        let events = state.choose(Choice::Assist(0)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Assist(0),
+            TurnSummary(summary(1)),
+            ActiveOperator(0),
+            ChoiceState(ChooseAction(0)),
+        ]);
+    }
+
+    #[test]
+    fn choose_idle() {
+        let mut state = initial_state_easy();
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Idle(0),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].idle).is_true();
+        assert_that(&state.active_operator).is_equal_to(1);
+    }
+
+    #[test]
+    fn choose_idle_skips_idle_operators() {
+        let mut state = TableState::setup_game(&GameConfig::new(Easy, get_operators(4)).unwrap());
+        state.operators[1].idle = true;
+        state.operators[2].idle = true;
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Idle(0),
+            TurnSummary(summary(0)),
+            ActiveOperator(3),
+            ChoiceState(ChooseAction(3)),
+        ]);
+    }
+
+    #[test]
+    fn turn_summary_face_and_backtrace() {
+        let mut state = initial_state_easy();
+        state.hackers = HackerDeck::from_iter([HackerCard::new(12)]);
+        let events = state.apply_all([Choice::Face, Choice::Backtrace]).unwrap();
+        let summary = TurnSummary {
+            faced: ArrayVec::from_iter([12]),
+            firewall_delta: -1,
+            ..backtrace_summary(0, &[12])
+        };
+        assert!(events[1].contains(&TurnSummary(summary)));
+        assert_that(&state.turn_summary).is_equal_to(TurnSummary::default());
+    }
+
+    #[test]
+    fn turn_summary_excludes_round_end() {
+        let mut state = round_end_state();
+        let events = state.choose(Choice::Idle).unwrap();
+        let summaries: Vec<&TableEvent> = events
+            .iter()
+            .filter(|x| matches!(x, TurnSummary(_)))
+            .collect();
+        assert_that(&summaries).is_equal_to(vec![&TurnSummary(summary(1))]);
+        assert_that(&state.turn_summary).is_equal_to(TurnSummary::default());
+    }
+
+    #[test]
+    fn end_turn_last_active_operator() {
+        let mut state = TableState::setup_game(&GameConfig::new(Easy, get_operators(3)).unwrap());
+        state.operators[1].idle = true;
+        state.operators[2].idle = true;
+        let mut events = Vec::new();
+        state.end_turn(&mut events);
+        assert_that(&events)
+            .is_equal_to(vec![TurnSummary(summary(0)), ChoiceState(ChooseAction(0))]);
+    }
+
+    #[test_case(0, &[], Some(1))]
+    #[test_case(3, &[], Some(0); "wraps around")]
+    #[test_case(1, &[2, 3], Some(0); "skips idle")]
+    #[test_case(1, &[0, 2, 3], Some(1); "only active left")]
+    #[test_case(1, &[0, 1, 2, 3], None; "all idle")]
+    fn next_operator(active: OperatorID, idle: &[usize], expected: Option<OperatorID>) {
+        let mut state = TableState::setup_game(&GameConfig::new(Easy, get_operators(4)).unwrap());
+        state.active_operator = active;
+        for i in idle {
+            state.operators[*i].idle = true;
+        }
+        assert_that(&state.next_operator()).is_equal_to(expected);
+    }
+
+    /// Initial state where every operator but the active operator 1 is idle
+    fn round_end_state() -> TableState {
+        let mut state = initial_state_easy();
+        state.active_operator = 1;
+        state.choice_state = ChooseAction(1);
+        state.operators[0].idle = true;
+        state
+    }
+
+    #[test]
+    fn choose_idle_ends_round() {
+        let mut state = round_end_state();
+        state.operators[0].secure_slots = [8, 17, 3];
+        state.operators[0].backtrace_list.push(26);
+        state.operators[1].secure_slots = [NO_HACKER, 4, NO_HACKER];
+        state.operators[1].skills.push(Stone);
+        state.operators[0].skills.clear();
+        state.breach = HackerDeck::from_iter([HackerCard::new(0), HackerCard::new(3)]);
+        let hackers = state.hackers.len();
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Idle(1),
+            TurnSummary(summary(1)),
+            // operator 1 has no keyboard or database secured
+            FirewallDelta(-1),
+            DatabaseRemove(0),
+            // virus in the breach
+            FirewallDelta(-1),
+            ReturnAssists,
+            DiscardBoard(0),
+            DiscardBoard(1),
+            RoundEnd,
+            BreachToStack,
+            AddLieutenants(2),
+            ShuffleHackers,
+            ActiveOperator(0),
+            ChoiceState(ChooseAction(0)),
+        ]);
+        assert_that(&state.round).is_equal_to(1);
+        assert_that(&state.firewalls).is_equal_to(3);
+        assert_that(&state.databases).is_equal_to([false, true, true]);
+        assert_that(&state.discard.len()).is_equal_to(5);
+        assert!(state.breach.is_empty());
+        assert_that(&state.hackers.len()).is_equal_to(hackers + 4);
+        let lieutenants = state
+            .hackers
+            .iter()
+            .filter(|x| defs::hacker(x.hacker).value() == 5);
+        assert_that(&lieutenants.count()).is_equal_to(2);
+        assert!(state.hackers.iter().any(|x| x.hacker == 0));
+        assert!(state.hackers.iter().all(|x| !x.face_up));
+        for operator in state.operators.iter() {
+            assert!(!operator.idle);
+            assert_that(&operator.skills.to_vec()).is_equal_to(vec![operator.operator]);
+            assert_that(&operator.secure_slots).is_equal_to([NO_HACKER; 3]);
+            assert!(operator.backtrace_list.is_empty());
+        }
+    }
+
+    #[test]
+    fn round_end_compromise_without_firewalls() {
+        let mut state = round_end_state();
+        state.firewalls = 0;
+        state.operators[0].secure_slots = [8, 17, 3];
+        state.operators[1].secure_slots = [NO_HACKER, NO_HACKER, 3];
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events[2..4].to_vec())
+            .is_equal_to(vec![WebserviceRemove(0), WebserviceRemove(1)]);
+    }
+
+    #[test]
+    fn round_end_adds_bosses() {
+        let mut state = round_end_state();
+        state.round = 1;
+        state.operators[0].secure_slots = [8, 17, 3];
+        state.operators[1].secure_slots = [8, 17, 3];
+        state.hackers.clear();
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events[events.len() - 4..].to_vec()).is_equal_to(vec![
+            AddBosses,
+            ShuffleHackers,
+            ActiveOperator(0),
+            ChoiceState(ChooseAction(0)),
+        ]);
+        assert_that(&state.round).is_equal_to(2);
+        assert_that(
+            &state
+                .hackers
+                .iter()
+                .map(|x| x.hacker)
+                .collect::<Vec<HackerID>>(),
+        )
+        .is_equal_to(state.bosses.to_vec());
+    }
+
+    #[test]
+    fn round_end_last_round_wins() {
+        let mut state = round_end_state();
+        state.round = 2;
+        state.operators[0].secure_slots = [8, 17, 3];
+        state.operators[1].secure_slots = [8, 17, 3];
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events.last()).is_equal_to(Some(&GameOver(GameResult::Win)));
+        assert_that(&state.result()).is_equal_to(Some(GameResult::Win));
+        assert_that(&state.choice_state).is_equal_to(ChoiceState::GameOver);
+        assert!(state.valid_choices().is_empty());
+    }
+
+    #[test]
+    fn round_end_loses_databases() {
+        let mut state = round_end_state();
+        state.databases = [false, false, true];
+        state.operators[0].secure_slots = [8, 17, 3];
+        state.operators[1].secure_slots = [8, 17, NO_HACKER];
+        state.operators[1].burnout = true;
+        let events = state.choose(Choice::Idle).unwrap();
+        let lost = GameOver(GameResult::Loss(LossReason::Databases));
+        assert_that(&events).is_equal_to(vec![
+            Idle(1),
+            TurnSummary(summary(1)),
+            DatabaseRemove(2),
+            lost,
+        ]);
+        assert!(!state.operators[1].desperation);
+    }
+
+    #[test]
+    fn backtrace_overwhelms() {
+        // Sniper's track is 9: 26 (value 3) + 39 (value 4) + faced 52 (value 5)
+        let mut state = initial_state_easy();
+        state.active_operator = 1;
+        state.operators[1].backtrace_list = ArrayVec::from_iter([26, 39]);
+        state.facing = 52;
+        state.choice_state = ChoiceState::Face(1);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        let lost = GameOver(GameResult::Loss(LossReason::Overwhelmed(1)));
+        assert_that(&events).is_equal_to(vec![Backtrace(1), lost]);
+        assert_that(&state.choose(Choice::Idle))
+            .is_equal_to(Err(ChoiceError::InvalidChoice(Choice::Idle)));
+    }
+
+    #[test]
+    fn round_end_desperation() {
+        let mut state = round_end_state();
+        state.operators[0].secure_slots = [8, 17, 3];
+        state.operators[1].secure_slots = [8, 17, 3];
+        state.operators[0].burnout = true;
+        let events = state.choose(Choice::Idle).unwrap();
+        assert!(events.contains(&Desperation(0)));
+        assert!(state.operators[0].desperation);
+        assert!(state.operators[0].burnout);
+        assert!(!state.operators[1].desperation);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot end round, operator 1 is not idle")]
+    fn perform_round_end_not_idle() {
+        let mut state = round_end_state();
+        state.perform(RoundEnd);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot end round 2, it is the last round")]
+    fn perform_round_end_last_round() {
+        let mut state = round_end_state();
+        state.round = 2;
+        state.perform(RoundEnd);
+    }
+
+    /// Summary of a turn of the indicated operator in which nothing was placed or changed
+    fn summary(operator: OperatorID) -> TurnSummary {
+        TurnSummary {
+            operator,
+            ..TurnSummary::default()
+        }
+    }
+
+    /// Summary of a turn of the indicated operator in which the indicated hackers were
+    /// backtraced, suffering their penalties, and nothing else changed
+    fn backtrace_summary(operator: OperatorID, hackers: &[HackerID]) -> TurnSummary {
+        TurnSummary {
+            backtraced: hackers.iter().copied().collect(),
+            penalties: hackers
+                .iter()
+                .map(|x| *defs::hacker(*x).penalty())
+                .filter(|x| *x != Penalty::NoPenalty)
+                .collect(),
+            ..summary(operator)
+        }
+    }
+
+    /// Initial state where operator 0 is facing the indicated hacker
+    fn facing_state(hacker: HackerID) -> TableState {
+        let mut state = initial_state_easy();
+        state.facing = hacker;
+        state.choice_state = ChoiceState::Face(0);
+        state
+    }
+
+    #[test_case(3, vec![Choice::Secure, Choice::Backtrace]; "database")]
+    #[test_case(8, vec![Choice::Secure, Choice::Backtrace]; "keyboard")]
+    #[test_case(12, vec![Choice::Backtrace]; "no symbol")]
+    fn valid_choice_face(hacker: HackerID, expected: Vec<Choice>) {
+        let state = facing_state(hacker);
+        assert_that(&state.valid_choices()).is_equal_to(expected);
+    }
+
+    #[test]
+    fn valid_choice_face_slot_taken() {
+        let mut state = facing_state(3);
+        state.operators[0].secure_slots[2] = 16;
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::Backtrace]);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::Backtrace));
+    }
+
+    #[test]
+    fn choose_skill_stone() {
+        let mut state = initial_state_easy();
+        // both value 2
+        state.operators[0].backtrace_list = ArrayVec::from_iter([13]);
+        state.hackers = HackerDeck::from_iter([HackerCard::new(16)]);
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events[2]).is_equal_to(ChoiceState(ChoiceState::Skill(0)));
+        assert_that(&state.valid_choices())
+            .is_equal_to(vec![Choice::UseSkill(Stone), Choice::DeclineSkill]);
+
+        let events = state.choose(Choice::UseSkill(Stone)).unwrap();
+        let summary = TurnSummary {
+            faced: ArrayVec::from_iter([16]),
+            ..summary(0)
+        };
+        assert_that(&events).is_equal_to(vec![
+            DiscardFacing,
+            TurnSummary(summary),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.discard.to_vec()).is_equal_to(vec![HackerCard {
+            hacker: 16,
+            face_up: true,
+        }]);
+        assert_that(&state.operators[0].backtrace_list.to_vec()).is_equal_to(vec![13]);
+    }
+
+    #[test]
+    fn skill_stone_no_matching_value() {
+        let mut state = initial_state_easy();
+        state.operators[0].backtrace_list = ArrayVec::from_iter([26]);
+        state.hackers = HackerDeck::from_iter([HackerCard::new(16)]);
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events[2]).is_equal_to(ChoiceState(ChoiceState::Face(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot discard, not facing a hacker")]
+    fn perform_discard_facing_not_facing() {
+        let mut state = initial_state_easy();
+        state.perform(DiscardFacing);
+    }
+
+    fn rogue_state() -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Rogue, Stone]));
+        let mut state = TableState::setup_game(&config.unwrap());
+        state.hackers = HackerDeck::from_iter([HackerCard::new(16), HackerCard::new(3)]);
+        state
+    }
+
+    #[test]
+    fn choose_skill_rogue() {
+        let mut state = rogue_state();
+        state.choose(Choice::Face).unwrap();
+        let events = state.choose(Choice::Secure).unwrap();
+        assert_that(&events).is_equal_to(vec![Secure(0), ChoiceState(ChoiceState::Skill(0))]);
+        assert_that(&state.valid_choices())
+            .is_equal_to(vec![Choice::UseSkill(Rogue), Choice::DeclineSkill]);
+
+        let events = state.choose(Choice::UseSkill(Rogue)).unwrap();
+        assert_that(&events).is_equal_to(vec![ActAgain, ChoiceState(ChooseAction(0))]);
+
+        // second action ends the turn, summarizing both
+        state.choose(Choice::Face).unwrap();
+        let events = state.choose(Choice::Backtrace).unwrap();
+        let summary = TurnSummary {
+            faced: ArrayVec::from_iter([3, 16]),
+            secured: ArrayVec::from_iter([3]),
+            ..backtrace_summary(0, &[16])
+        };
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            TurnSummary(summary),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert!(!state.acting_again);
+    }
+
+    #[test]
+    fn choose_skill_rogue_decline() {
+        let mut state = rogue_state();
+        state.apply_all([Choice::Face, Choice::Secure]).unwrap();
+        let events = state.choose(Choice::DeclineSkill).unwrap();
+        let summary = TurnSummary {
+            faced: ArrayVec::from_iter([3]),
+            secured: ArrayVec::from_iter([3]),
+            ..summary(0)
+        };
+        assert_that(&events).is_equal_to(vec![
+            TurnSummary(summary),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+    }
+
+    #[test]
+    fn skill_rogue_not_after_idle() {
+        let mut state = rogue_state();
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events[1]).is_equal_to(TurnSummary(summary(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "operator 0 is already acting again this turn")]
+    fn perform_act_again_twice() {
+        let mut state = rogue_state();
+        state.perform(ActAgain);
+        state.perform(ActAgain);
+    }
+
+    fn pass_state(operators: &[OperatorType], hacker: HackerID) -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter(operators.to_vec()));
+        let mut state = TableState::setup_game(&config.unwrap());
+        state.hackers = HackerDeck::from_iter([HackerCard::new(hacker)]);
+        state
+    }
+
+    #[test]
+    fn choose_skill_charm() {
+        // value 2
+        let mut state = pass_state(&[Charm, Stone, Biggs], 16);
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events[2]).is_equal_to(ChoiceState(ChoiceState::Skill(0)));
+
+        let events = state.choose(Choice::UseSkill(Charm)).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::Pass(0))]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::PassTo(1), Choice::PassTo(2)]);
+
+        let events = state.choose(Choice::PassTo(2)).unwrap();
+        assert_that(&events).is_equal_to(vec![PassFacing(2), ChoiceState(ChoiceState::Face(2))]);
+        let events = state.choose(Choice::Secure).unwrap();
+        let summary = TurnSummary {
+            faced: ArrayVec::from_iter([16]),
+            secured: ArrayVec::from_iter([16]),
+            ..summary(0)
+        };
+        assert_that(&events).is_equal_to(vec![
+            Secure(2),
+            TurnSummary(summary),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[2].secure_slots[2]).is_equal_to(16);
+    }
+
+    #[test]
+    fn pass_completes_neighbor_track() {
+        // Sniper's track is 9: 26 (value 3) + 39 (value 4) + passed 16 (value 2)
+        let mut state = pass_state(&[Charm, Sniper], 16);
+        state.operators[1].backtrace_list = ArrayVec::from_iter([26, 39]);
+        // Charm's track is also 9, so Charm mustn't be offered their Flow instead
+        state.operators[0].backtrace_list = ArrayVec::from_iter([27, 40, 13]);
+        state
+            .apply_all([Choice::Face, Choice::UseSkill(Charm), Choice::PassTo(1)])
+            .unwrap();
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![Backtrace(1), ChoiceState(ChoiceState::Flow(1))]);
+
+        let events = state.choose(Choice::DeclineFlow).unwrap();
+        assert_that(&events[1..].to_vec())
+            .is_equal_to(vec![ActiveOperator(1), ChoiceState(ChooseAction(1))]);
+        assert_that(&state.passed_to).is_none();
+    }
+
+    #[test]
+    fn skill_biggs_odd_only() {
+        // value 2
+        let mut state = pass_state(&[Biggs, Stone], 16);
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events[2]).is_equal_to(ChoiceState(ChoiceState::Face(0)));
+
+        // value 1, only one neighbor with two operators
+        let mut state = pass_state(&[Biggs, Stone], 3);
+        state
+            .apply_all([Choice::Face, Choice::UseSkill(Biggs)])
+            .unwrap();
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::PassTo(1)]);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::PassTo(1)));
+    }
+
+    #[test]
+    fn skill_pass_needs_neighbor() {
+        let mut state = pass_state(&[Biggs], 3);
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events[2]).is_equal_to(ChoiceState(ChoiceState::Face(0)));
+    }
+
+    #[test_case(4, 0, &[1, 3])]
+    #[test_case(4, 3, &[0, 2])]
+    #[test_case(2, 1, &[0])]
+    fn neighbors(count: usize, operator: OperatorID, expected: &[OperatorID]) {
+        let state = pass_state(&OPERATOR_TYPES[..count], 3);
+        let neighbors: Vec<OperatorID> = state.neighbors(operator).collect();
+        assert_that(&neighbors).is_equal_to(expected.to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot pass, not facing a hacker")]
+    fn perform_pass_facing_not_facing() {
+        let mut state = initial_state_easy();
+        state.perform(PassFacing(1));
+    }
+
+    fn rich_state() -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Rich, Stone]));
+        let mut state = TableState::setup_game(&config.unwrap());
+        state.hackers = HackerDeck::from_iter([HackerCard::new(3), HackerCard::new(16)]);
+        state
+    }
+
+    #[test]
+    fn choose_skill_rich() {
+        let mut state = rich_state();
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events[2]).is_equal_to(ChoiceState(ChoiceState::Skill(0)));
+
+        let events = state.choose(Choice::UseSkill(Rich)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            BottomDeckFacing,
+            FaceStarted,
+            FaceRevealed(3),
+            ChoiceState(ChoiceState::Face(0)),
+        ]);
+        assert_that(&state.hackers.to_vec()).is_equal_to(vec![HackerCard::new(16)]);
+        assert_that(&state.facing).is_equal_to(3);
+    }
+
+    #[test]
+    fn choose_skill_rich_delay_reveal() {
+        let mut state = rich_state();
+        state.options.delay_reveal = true;
+        state.apply_all([Choice::Face, Choice::Reveal]).unwrap();
+
+        let events = state.choose(Choice::UseSkill(Rich)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            BottomDeckFacing,
+            FaceStarted,
+            ChoiceState(ChoiceState::Reveal(0, false)),
+        ]);
+        // the new hacker must be faced, even though Rich's skill applies to it
+        let events = state.choose(Choice::Reveal).unwrap();
+        assert_that(&events).is_equal_to(vec![FaceRevealed(3), ChoiceState(ChoiceState::Face(0))]);
+    }
+
+    #[test]
+    fn skill_rich_needs_another_hacker() {
+        let mut state = rich_state();
+        state.hackers.remove(0);
+        let events = state.choose(Choice::Face).unwrap();
+        assert_that(&events[2]).is_equal_to(ChoiceState(ChoiceState::Face(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot bottom-deck, not facing a hacker")]
+    fn perform_bottom_deck_facing_not_facing() {
+        let mut state = initial_state_easy();
+        state.perform(BottomDeckFacing);
+    }
+
+    #[test]
+    fn choose_secure() {
+        let mut state = facing_state(3);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::Secure));
+        let events = state.choose(Choice::Secure).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Secure(0),
+            TurnSummary(TurnSummary {
+                secured: ArrayVec::from_iter([3]),
+                ..summary(0)
+            }),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].secure_slots).is_equal_to([NO_HACKER, NO_HACKER, 3]);
+        assert_that(&state.facing).is_equal_to(NO_HACKER);
+    }
+
+    #[test]
+    fn choose_backtrace() {
+        let mut state = facing_state(3);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            TurnSummary(backtrace_summary(0, &[3])),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].backtrace_list.to_vec()).is_equal_to(vec![3]);
+        assert_that(&state.facing).is_equal_to(NO_HACKER);
+    }
+
+    /// State with 3 operators, none of whom ignore penalties, where operator 1 is facing
+    /// the indicated hacker, on top of a hacker stack of the indicated hackers
+    fn draw_state(hacker: HackerID, hackers: &[HackerID]) -> TableState {
+        let operators = ArrayVec::from_iter([Stone, Biggs, Charm]);
+        let mut state = TableState::setup_game(&GameConfig::new(Easy, operators).unwrap());
+        state.active_operator = 1;
+        state.facing = hacker;
+        state.choice_state = ChoiceState::Face(1);
+        state.hackers = HackerDeck::from_iter(hackers.iter().map(|x| HackerCard::new(*x)));
+        state
+    }
+
+    #[test_case(29, 2; "left")]
+    #[test_case(41, 0; "right")]
+    fn choose_backtrace_draw_penalty(hacker: HackerID, neighbor: OperatorID) {
+        let mut state = draw_state(hacker, &[16, 3]);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(1),
+            DrawToBacktrace(neighbor),
+            TurnSummary(backtrace_summary(1, &[hacker, 3])),
+            ActiveOperator(2),
+            ChoiceState(ChooseAction(2)),
+        ]);
+        let backtrace = &state.operators[neighbor as usize].backtrace_list;
+        assert_that(&backtrace.to_vec()).is_equal_to(vec![3]);
+        assert_that(&state.hackers.len()).is_equal_to(1);
+    }
+
+    #[test]
+    fn choose_backtrace_draw_penalty_chains() {
+        // 29 makes operator 2 draw 41, which makes operator 1 draw 3
+        let mut state = draw_state(29, &[3, 41]);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[..3].to_vec()).is_equal_to(vec![
+            Backtrace(1),
+            DrawToBacktrace(2),
+            DrawToBacktrace(1),
+        ]);
+        assert_that(&state.operators[1].backtrace_list.to_vec()).is_equal_to(vec![29, 3]);
+    }
+
+    #[test]
+    fn choose_backtrace_draw_penalty_empty_stack() {
+        let mut state = draw_state(29, &[]);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(1),
+            ShortDraw(1, 1, 0),
+            TurnSummary(backtrace_summary(1, &[29])),
+            ActiveOperator(2),
+            ChoiceState(ChooseAction(2)),
+        ]);
+    }
+
+    #[test]
+    fn choose_backtrace_compromise() {
+        let mut state = facing_state(12);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            FirewallDelta(-1),
+            TurnSummary(TurnSummary {
+                firewall_delta: -1,
+                ..backtrace_summary(0, &[12])
+            }),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+    }
+
+    #[test]
+    fn choose_backtrace_compromise_webservice() {
+        let mut state = facing_state(12);
+        state.firewalls = 0;
+        state.webservices = [false, true, false, true, true, false];
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            ChoiceState(ChoiceState::Webservice(0, 0)),
+        ]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![
+            Choice::TakeDownWebservice(1),
+            Choice::TakeDownWebservice(3),
+            Choice::TakeDownWebservice(4),
+        ]);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::TakeDownWebservice(1)));
+
+        let events = state.choose(Choice::TakeDownWebservice(3)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            WebserviceRemove(3),
+            TurnSummary(TurnSummary {
+                webservice_delta: -1,
+                ..backtrace_summary(0, &[12])
+            }),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.webservices).is_equal_to([false, true, false, false, true, false]);
+    }
+
+    #[test]
+    fn choose_backtrace_compromise_last_webservice() {
+        let mut state = facing_state(12);
+        state.firewalls = 0;
+        state.webservices = [false, false, false, false, true, false];
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            WebserviceRemove(4),
+            GameOver(GameResult::Loss(LossReason::Webservices)),
+        ]);
+    }
+
+    #[test]
+    fn choose_backtrace_compromise_webservice_5() {
+        let mut state = facing_state(12);
+        state.firewalls = 0;
+        state.webservices = [false, true, false, false, false, true];
+        state.choose(Choice::Backtrace).unwrap();
+        let events = state.choose(Choice::TakeDownWebservice(5)).unwrap();
+        assert_that(&events[0]).is_equal_to(WebserviceRemove(5));
+        assert_that(&state.webservices).is_equal_to([false, true, false, false, false, false]);
+    }
+
+    #[test]
+    fn choose_backtrace_compromise_webservice_5_last() {
+        let mut state = facing_state(12);
+        state.firewalls = 0;
+        state.webservices = [false, false, false, false, false, true];
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            WebserviceRemove(5),
+            GameOver(GameResult::Loss(LossReason::Webservices)),
+        ]);
+    }
+
+    #[test]
+    fn choose_backtrace_double_compromise() {
+        let mut state = facing_state(65);
+        state.firewalls = 1;
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            FirewallDelta(-1),
+            ChoiceState(ChoiceState::Webservice(0, 0)),
+        ]);
+
+        state.firewalls = 0;
+        state.choice_state = ChoiceState::Webservice(0, 1);
+        let events = state.choose(Choice::TakeDownWebservice(0)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            WebserviceRemove(0),
+            ChoiceState(ChoiceState::Webservice(0, 0)),
+        ]);
+    }
+
+    #[test_case(2, &[3, 16], vec![Breach]; "ninja")]
+    #[test_case(60, &[3, 16, 26], vec![Breach, Breach]; "double ninja")]
+    #[test_case(60, &[3], vec![Breach, ShortDraw(0, 2, 1)]; "double ninja runs out")]
+    #[test_case(2, &[], vec![ShortDraw(0, 1, 0)]; "empty hacker stack")]
+    fn choose_backtrace_ninja(hacker: HackerID, hackers: &[HackerID], expected: Vec<TableEvent>) {
+        let mut state = facing_state(hacker);
+        state.hackers = HackerDeck::from_iter(hackers.iter().map(|x| HackerCard::new(*x)));
+        let events = state.choose(Choice::Backtrace).unwrap();
+        let breached = expected.iter().filter(|x| **x == Breach).count();
+        assert_that(&events[1..events.len() - 3].to_vec()).is_equal_to(expected);
+        assert_that(&state.breach.len()).is_equal_to(breached);
+        assert_that(&state.hackers.len()).is_equal_to(hackers.len() - breached);
+        assert!(state.breach.iter().all(|x| !x.face_up));
+        if breached > 0 {
+            assert_that(&state.breach[0].hacker).is_equal_to(hackers[hackers.len() - 1]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "operator 0 has 12 hackers left to draw")]
+    fn perform_short_draw_with_hackers() {
+        let mut state = initial_state_easy();
+        state.perform(ShortDraw(0, 1, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot breach, hacker deck is empty")]
+    fn perform_breach_empty_deck() {
+        let mut state = initial_state_easy();
+        state.hackers.clear();
+        state.perform(Breach);
+    }
+
+    #[test]
+    fn choose_backtrace_no_secure() {
+        let mut state = facing_state(11);
+        state.hackers.push(HackerCard::new(16));
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[..2].to_vec()).is_equal_to(vec![Backtrace(0), NoSecure(0)]);
+        assert!(state.operator_summary(0).no_secure);
+
+        // still can't secure on their next turn, as they haven't idled
+        state.apply_all([Choice::Idle, Choice::Face]).unwrap();
+        assert_that(&state.choice_state).is_equal_to(ChoiceState::Face(0));
+        assert_that(&state.facing).is_equal_to(16);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::Backtrace]);
+    }
+
+    #[test]
+    fn choose_backtrace_no_give_assist() {
+        let mut state = facing_state(6);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[..2].to_vec()).is_equal_to(vec![Backtrace(0), NoGiveAssist(0)]);
+        assert!(!state.operator_summary(0).can_assist);
+
+        state.active_operator = 0;
+        state.choice_state = ChooseAction(0);
+        assert!(!state.valid_choices().contains(&Choice::Assist(1)));
+    }
+
+    #[test]
+    fn choose_backtrace_idle_penalty() {
+        let mut state = facing_state(59);
+        state.operators[0].no_secure = true;
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            ClearStatuses(0),
+            Idle(0),
+            TurnSummary(backtrace_summary(0, &[59])),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert!(state.operators[0].idle);
+    }
+
+    #[test]
+    fn choose_backtrace_idle_penalty_neighbor() {
+        let mut state = draw_state(29, &[59]);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(1),
+            DrawToBacktrace(2),
+            Idle(2),
+            TurnSummary(backtrace_summary(1, &[29, 59])),
+            ActiveOperator(0),
+            ChoiceState(ChooseAction(0)),
+        ]);
+    }
+
+    #[test]
+    fn choose_idle_clears_statuses() {
+        let mut state = initial_state_easy();
+        state.operators[0].no_secure = true;
+        state.operators[0].no_give_assist = true;
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            ClearStatuses(0),
+            Idle(0),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert!(!state.operators[0].no_secure);
+        assert!(!state.operators[0].no_give_assist);
+    }
+
+    #[test]
+    fn choose_backtrace_burnout() {
+        let mut state = facing_state(0);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            Burnout(0),
+            TurnSummary(backtrace_summary(0, &[0])),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert!(state.operator_summary(0).burnout);
+    }
+
+    #[test]
+    fn choose_backtrace_second_burnout() {
+        let mut state = facing_state(0);
+        state.operators[0].burnout = true;
+        let events = state.choose(Choice::Backtrace).unwrap();
+        let lost = GameResult::Loss(LossReason::BurnedOut(0));
+        assert_that(&events).is_equal_to(vec![Backtrace(0), GameOver(lost)]);
+        assert_that(&state.result()).is_equal_to(Some(lost));
+    }
+
+    #[test_case(63, NoGiveAssist(0))]
+    #[test_case(61, NoTalent(0))]
+    fn choose_backtrace_status_and_burnout(hacker: HackerID, status: TableEvent) {
+        let mut state = facing_state(hacker);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[..3].to_vec()).is_equal_to(vec![Backtrace(0), status, Burnout(0)]);
     }
 
-    fn firewall_delta(initial: u8, delta: i8) -> TableState {
+    #[test]
+    fn no_talent() {
         let mut state = initial_state_easy();
-        state.firewalls = initial;
-        state.perform(FirewallDelta(delta));
+        state.perform(NoTalent(0));
+        assert!(state.operator_summary(0).no_talent);
+        assert_that(&state.applicable_skills(0).count()).is_equal_to(0);
+        let events = state.choose(Choice::Idle).unwrap();
+        assert_that(&events[0]).is_equal_to(ClearStatuses(0));
+        assert!(!state.operators[0].no_talent);
+    }
+
+    #[test]
+    #[should_panic(expected = "operator 0 already has a burnout token")]
+    fn perform_burnout_twice() {
+        let mut state = initial_state_easy();
+        state.perform(Burnout(0));
+        state.perform(Burnout(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "operator 0 has no burnout token to remove")]
+    fn perform_burnout_removed_none() {
+        let mut state = initial_state_easy();
+        state.perform(BurnoutRemoved(0));
+    }
+
+    fn charm_flow_state() -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Charm, Stone]));
+        let mut state = TableState::setup_game(&config.unwrap());
+        state.choice_state = ChoiceState::Flow(0);
         state
     }
 
-    #[test_case([false, true, false], 1, [false, false, false])]
-    #[test_case([true, true, false], 0, [false, true, false])]
-    #[test_case([true, true, true], 2, [true, true, false])]
-    fn perform_database_remove_valid(initial: [bool; 3], delta: u8, expected: [bool; 3]) {
-        let state = database_remove(initial, delta);
-        assert_that(&state.databases).is_equal_to(&expected);
+    #[test]
+    fn choose_flow_charm() {
+        let mut state = charm_flow_state();
+        state.firewalls = 0;
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            FirewallDelta(1),
+            TurnSummary(TurnSummary {
+                firewall_delta: 1,
+                ..summary(0)
+            }),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
     }
 
     #[test]
-    #[should_panic(expected = "database index out of range, must be 0..=2, was 3")]
-    fn perform_database_remove_invalid() {
-        database_remove([true, false, true], 3);
+    fn choose_flow_charm_max_firewalls() {
+        let mut state = charm_flow_state();
+        state.firewalls = state.max_firewalls;
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
     }
 
     #[test]
-    #[should_panic(expected = "database index 1 already removed")]
-    fn perform_database_remove_invalid_2() {
-        database_remove([true, false, true], 1);
+    fn choose_flow_charm_desperation() {
+        let mut state = charm_flow_state();
+        state.firewalls = state.max_firewalls;
+        state.operators[0].desperation = true;
+        state.operators[0].burnout = true;
+        state.operators[1].burnout = true;
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::CharmDesperationFlow)]);
+        assert_that(&state.valid_choices())
+            .is_equal_to(vec![Choice::RemoveBurnout(0), Choice::RemoveBurnout(1)]);
+
+        let events = state.choose(Choice::RemoveBurnout(1)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            BurnoutRemoved(1),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert!(!state.operators[1].burnout);
+        assert!(state.operators[0].burnout);
     }
 
-    fn database_remove(initial: [bool; 3], idx: u8) -> TableState {
+    #[test]
+    #[should_panic(expected = "cannot secure, HackerID 12 has no symbol")]
+    fn perform_secure_no_symbol() {
+        let mut state = facing_state(12);
+        state.perform(Secure(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot secure, operator 0 slot 2 already has HackerID 16")]
+    fn perform_secure_slot_taken() {
+        let mut state = facing_state(3);
+        state.operators[0].secure_slots[2] = 16;
+        state.perform(Secure(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot secure, not facing a hacker")]
+    fn perform_secure_not_facing() {
         let mut state = initial_state_easy();
-        state.databases = initial;
-        state.perform(DatabaseRemove(idx));
+        state.perform(Secure(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot backtrace, not facing a hacker")]
+    fn perform_backtrace_not_facing() {
+        let mut state = initial_state_easy();
+        state.perform(Backtrace(0));
+    }
+
+    #[test]
+    fn valid_choice_skill_none_applicable() {
+        let mut state = facing_state(3);
+        state.choice_state = ChoiceState::Skill(0);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::DeclineSkill]);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::DeclineSkill));
+    }
+
+    #[test]
+    fn choose_decline_skill() {
+        let mut state = facing_state(3);
+        state.choice_state = ChoiceState::Skill(0);
+        let events = state.choose(Choice::DeclineSkill).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::Face(0))]);
+        assert_that(&state.facing).is_equal_to(3);
+    }
+
+    #[test]
+    fn choose_skill_not_held() {
+        let mut state = facing_state(3);
+        state.choice_state = ChoiceState::Skill(0);
+        let result = state.choose(Choice::UseSkill(Rich));
+        assert_that(&result).is_equal_to(Err(ChoiceError::InvalidChoice(Choice::UseSkill(Rich))));
+    }
+
+    /// Initial state where operator 0 must discard from the indicated secure slots
+    fn discard_state(secure_slots: [HackerID; 3]) -> TableState {
+        let mut state = initial_state_easy();
+        state.operators[0].secure_slots = secure_slots;
+        state.choice_state = ChoiceState::DiscardLeft(0);
         state
     }
 
-    #[test_case([false, true, false, false, false, false], 1, [false, false, false, false, false, false])]
-    #[test_case([true, true, false, false, false, false], 0, [false, true, false, false, false, false])]
-    #[test_case([true, true, true, false, false, false], 2, [true, true, false, false, false, false])]
-    fn perform_webservice_remove_valid(initial: [bool; 6], delta: u8, expected: [bool; 6]) {
-        let state = webservice_remove(initial, delta);
-        assert_that(&state.webservices).is_equal_to(&expected);
+    #[test]
+    fn choose_backtrace_hacker_revive() {
+        let mut state = facing_state(64);
+        state.discard = HackerDeck::from_iter([HackerCard::new(3), HackerCard::new(16)]);
+        let hackers = state.hackers.len();
+        let mut other = state.clone();
+        let events = state.choose(Choice::Backtrace).unwrap();
+        let (revived, position) = match events[2] {
+            HackerRevived(hacker, position) => (hacker, position),
+            _ => panic!("expected HackerRevived, got {:?}", events[2]),
+        };
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            NoSecure(0),
+            HackerRevived(revived, position),
+            TurnSummary(backtrace_summary(0, &[64])),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        let remaining = if revived == 3 { 16 } else { 3 };
+        assert_that(&state.discard)
+            .is_equal_to(HackerDeck::from_iter([HackerCard::new(remaining)]));
+        assert_that(&state.hackers.len()).is_equal_to(hackers + 1);
+        assert_that(&state.hackers[position as usize]).is_equal_to(HackerCard::new(revived));
+
+        // same seed revives the same hacker to the same position
+        let other_events = other.choose(Choice::Backtrace).unwrap();
+        assert_that(&other_events).is_equal_to(&events);
     }
 
     #[test]
-    #[should_panic(expected = "webservice index out of range, must be 0..=5, was 6")]
-    fn perform_webservice_remove_invalid() {
-        webservice_remove([true, false, true, false, false, false], 6);
+    fn perform_hacker_revived() {
+        let mut state = initial_state_easy();
+        state.discard = HackerDeck::from_iter([HackerCard::new(3), HackerCard::new(16)]);
+        let hackers = state.hackers.len();
+        state.perform(HackerRevived(16, 1));
+        assert_that(&state.discard).is_equal_to(HackerDeck::from_iter([HackerCard::new(3)]));
+        assert_that(&state.hackers.len()).is_equal_to(hackers + 1);
+        assert_that(&state.hackers[1]).is_equal_to(HackerCard::new(16));
     }
 
     #[test]
-    #[should_panic(expected = "webservice index 1 already removed")]
-    fn perform_webservice_remove_invalid_2() {
-        webservice_remove([true, false, true, false, false, false], 1);
+    fn choose_backtrace_hacker_revive_empty_discard() {
+        let mut state = facing_state(64);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[..4].to_vec()).is_equal_to(vec![
+            Backtrace(0),
+            NoSecure(0),
+            TurnSummary(backtrace_summary(0, &[64])),
+            ActiveOperator(1),
+        ]);
     }
 
-    fn webservice_remove(initial: [bool; 6], idx: u8) -> TableState {
+    #[test]
+    #[should_panic(expected = "cannot revive, HackerID 3 is not in the discard pile")]
+    fn perform_hacker_revived_not_discarded() {
         let mut state = initial_state_easy();
-        state.webservices = initial;
-        state.perform(WebserviceRemove(idx));
+        state.discard = HackerDeck::from_iter([HackerCard::new(16)]);
+        state.perform(HackerRevived(3, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot revive to position 15, hacker stack only has 12")]
+    fn perform_hacker_revived_out_of_range() {
+        let mut state = initial_state_easy();
+        state.discard = HackerDeck::from_iter([HackerCard::new(3)]);
+        state.perform(HackerRevived(3, 15));
+    }
+
+    #[test_case(Sniper, 16, true; "sniper even")]
+    #[test_case(Sniper, 3, false; "sniper odd")]
+    #[test_case(Admin, 3, true; "admin odd")]
+    #[test_case(Admin, 16, false; "admin even")]
+    #[test_case(Stone, 16, false; "stone even")]
+    fn penalty_exemption(skill: OperatorType, hacker: HackerID, exempt: bool) {
+        let mut state = initial_state_easy();
+        state.operators[0].skills = ArrayVec::from_iter([Stone, skill]);
+        let value = defs::hacker(hacker).value();
+        assert_that(&state.operators[0].penalty_exemption(value).is_some()).is_equal_to(exempt);
+        let summary = state.operator_summary(0);
+        assert_that(&summary.ignored_penalty_values.contains(&value)).is_equal_to(exempt);
+    }
+
+    #[test]
+    fn choose_backtrace_sniper_ignores_even_penalty() {
+        // 52: value 5 compromise, 41: value 4 draw right
+        let mut state = initial_state_easy();
+        state.active_operator = 1;
+        state.facing = 41;
+        state.choice_state = ChoiceState::Face(1);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        let summary = TurnSummary {
+            backtraced: ArrayVec::from_iter([41]),
+            ..summary(1)
+        };
+        assert_that(&events[..3].to_vec()).is_equal_to(vec![
+            Backtrace(1),
+            PenaltyIgnored(1, Sniper),
+            TurnSummary(summary),
+        ]);
+
+        state.active_operator = 1;
+        state.facing = 52;
+        state.choice_state = ChoiceState::Face(1);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[1]).is_equal_to(FirewallDelta(-1));
+    }
+
+    #[test]
+    fn choose_backtrace_sniper_assist_ignores_even_penalty() {
+        let mut state = facing_state(41);
+        state.operators[0].skills.push(Sniper);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[1]).is_equal_to(PenaltyIgnored(0, Sniper));
+
+        state.active_operator = 0;
+        state.facing = 41;
+        state.choice_state = ChoiceState::Face(0);
+        state.operators[0].backtrace_list.clear();
+        state.operators[0].no_talent = true;
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[1]).is_equal_to(DrawToBacktrace(1));
+    }
+
+    #[test]
+    fn choose_backtrace_admin_ignores_odd_penalty() {
+        // 29: value 3 draw left
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Admin, Stone]));
+        let mut state = TableState::setup_game(&config.unwrap());
+        state.facing = 29;
+        state.choice_state = ChoiceState::Face(0);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[..2].to_vec())
+            .is_equal_to(vec![Backtrace(0), PenaltyIgnored(0, Admin)]);
+        assert!(state.operators[1].backtrace_list.is_empty());
+    }
+
+    #[test]
+    fn penalty_exemption_sniper_and_admin() {
+        let mut state = initial_state_easy();
+        state.operators[1].skills.push(Admin);
+        let summary = state.operator_summary(1);
+        assert_that(&summary.ignored_penalty_values.to_vec()).is_equal_to(vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    #[should_panic(expected = "operator 0 does not hold skill Sniper")]
+    fn perform_penalty_ignored_without_skill() {
+        let mut state = initial_state_easy();
+        state.perform(PenaltyIgnored(0, Sniper));
+    }
+
+    #[test]
+    fn choose_backtrace_discard_secure() {
+        let mut state = facing_state(62);
+        state.operators[0].secure_slots = [21, NO_HACKER, 3];
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events)
+            .is_equal_to(vec![Backtrace(0), ChoiceState(ChoiceState::DiscardLeft(0))]);
+
+        let events = state.choose(Choice::DiscardSecure(2)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardSecure(0, 2),
+            TurnSummary(backtrace_summary(0, &[62])),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].secure_slots).is_equal_to([21, NO_HACKER, NO_HACKER]);
+    }
+
+    #[test]
+    fn choose_backtrace_discard_secure_nothing_secured() {
+        let mut state = facing_state(62);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(0),
+            NothingToDiscard(0),
+            TurnSummary(backtrace_summary(0, &[62])),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "operator 0 has a hacker to discard in slot 2")]
+    fn perform_nothing_to_discard_secured() {
+        let mut state = discard_state([NO_HACKER, NO_HACKER, 3]);
+        state.perform(NothingToDiscard(0));
+    }
+
+    #[test]
+    fn valid_choice_discard_left() {
+        let state = discard_state([21, NO_HACKER, 3]);
+        assert_that(&state.valid_choices())
+            .is_equal_to(vec![Choice::DiscardSecure(0), Choice::DiscardSecure(2)]);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::DiscardSecure(2)));
+    }
+
+    #[test]
+    fn choose_discard_secure() {
+        let mut state = discard_state([21, NO_HACKER, 3]);
+        let events = state.choose(Choice::DiscardSecure(0)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardSecure(0, 0),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].secure_slots).is_equal_to([NO_HACKER, NO_HACKER, 3]);
+        assert_that(&state.discard.last().map(|x| x.hacker)).is_equal_to(Some(21));
+    }
+
+    #[test]
+    fn choose_discard_secure_empty_slot() {
+        let mut state = discard_state([21, NO_HACKER, 3]);
+        let result = state.choose(Choice::DiscardSecure(1));
+        assert_that(&result).is_equal_to(Err(ChoiceError::InvalidChoice(Choice::DiscardSecure(1))));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot discard, operator 0 slot 1 is empty")]
+    fn perform_discard_secure_empty_slot() {
+        let mut state = discard_state([21, NO_HACKER, 3]);
+        state.perform(DiscardSecure(0, 1));
+    }
+
+    /// Initial state with the repair variant, where operator 0 has secured a Webservice
+    /// and a Database hacker and the first webservice and database are down
+    fn repair_state() -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, get_operators(2))
+            .unwrap()
+            .with_options(RuleOptions {
+                repair: true,
+                ..RuleOptions::default()
+            });
+        let mut state = TableState::setup_game(&config);
+        state.operators[0].secure_slots = [NO_HACKER, 17, 3];
+        state.webservices[0] = false;
+        state.databases[0] = false;
         state
     }
 
     #[test]
-    fn perform_face() {
+    fn valid_choice_repair() {
+        let mut state = repair_state();
+        assert!(state.valid_choices().contains(&Choice::RepairWebservice));
+        assert!(state.valid_choices().contains(&Choice::RepairDatabase));
+
+        state.databases[0] = true;
+        state.operators[0].secure_slots[1] = NO_HACKER;
+        assert!(!state.valid_choices().contains(&Choice::RepairWebservice));
+        assert!(!state.valid_choices().contains(&Choice::RepairDatabase));
+    }
+
+    #[test]
+    fn valid_choice_repair_disabled() {
+        let mut state = repair_state();
+        state.options = RuleOptions::default();
+        assert!(!state.valid_choices().contains(&Choice::RepairWebservice));
+        assert!(!state.valid_choices().contains(&Choice::RepairDatabase));
+    }
+
+    #[test]
+    fn choose_repair_database() {
+        let mut state = repair_state();
+        let events = state.choose(Choice::RepairDatabase).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardSecure(0, 2),
+            DatabaseRepair(0),
+            TurnSummary(TurnSummary {
+                database_delta: 1,
+                ..summary(0)
+            }),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.databases).is_equal_to([true; 3]);
+        assert_that(&state.operators[0].secure_slots).is_equal_to([NO_HACKER, 17, NO_HACKER]);
+    }
+
+    #[test]
+    fn choose_repair_webservice() {
+        let mut state = repair_state();
+        let events = state.choose(Choice::RepairWebservice).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardSecure(0, 1),
+            WebserviceRepair(0),
+            TurnSummary(TurnSummary {
+                webservice_delta: 1,
+                ..summary(0)
+            }),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.webservices).is_equal_to([true; 6]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot repair database 1, not removed")]
+    fn perform_database_repair_not_removed() {
+        let mut state = repair_state();
+        state.perform(DatabaseRepair(1));
+    }
+
+    #[test]
+    fn choose_backtrace_reaches_track() {
+        // Sniper's track is 9: 26 (value 3) + 39 (value 4) + faced 16 (value 2)
         let mut state = initial_state_easy();
-        let mut expected_hackers = state.hackers.clone();
-        let expected_face = expected_hackers.pop().unwrap();
-        state.perform(Face);
-        assert_that(&state.hackers.iter()).equals_iterator(&expected_hackers.iter());
-        assert_that(&state.facing).is_equal_to(&expected_face.hacker);
+        state.active_operator = 1;
+        state.operators[1].backtrace_list = ArrayVec::from_iter([26, 39]);
+        state.facing = 16;
+        state.choice_state = ChoiceState::Face(1);
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![Backtrace(1), ChoiceState(ChoiceState::Flow(1))]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::UseFlow, Choice::DeclineFlow]);
+        assert_that(&state.default_choice()).is_equal_to(Some(Choice::DeclineFlow));
     }
 
     #[test]
-    #[should_panic(expected = "cannot face, hacker deck is empty")]
-    fn perform_face_invalid_deck() {
+    fn choose_backtrace_reaches_track_every_operator() {
+        // faced 16 (value 2) fills the track, with no penalties along the way
+        for operator in OPERATORS {
+            let config = GameConfig::new(Easy, ArrayVec::from_iter([operator]));
+            let mut state = TableState::setup_game(&config.unwrap());
+            let mut remaining = state.operators[0].track() - 2;
+            for hacker in [43, 26, 16, 3] {
+                if defs::hacker(hacker).value() <= remaining {
+                    remaining -= defs::hacker(hacker).value();
+                    state.operators[0].backtrace_list.push(hacker);
+                }
+            }
+            state.facing = 16;
+            state.choice_state = ChoiceState::Face(0);
+            let events = state.choose(Choice::Backtrace).unwrap();
+            assert_that(&events).is_equal_to(vec![Backtrace(0), ChoiceState(ChoiceState::Flow(0))]);
+        }
+    }
+
+    /// State with the indicated operators, where the first must decide whether to use
+    /// their flow
+    fn flow_state(operators: &[OperatorType], desperation: bool) -> TableState {
+        let config = GameConfig::new(Easy, ArrayVec::from_iter(operators.iter().copied()));
+        let mut state = TableState::setup_game(&config.unwrap());
+        state.operators[0].desperation = desperation;
+        state.choice_state = ChoiceState::Flow(0);
+        state
+    }
+
+    #[test]
+    fn choose_flow_stone() {
+        let mut state = flow_state(&[Stone, Sniper, Rogue], false);
+        state.operators[1].skills.clear();
+        state.operators[2].idle = true;
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::StoneFlow)]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![
+            Choice::GiveAssist(0, 1),
+            Choice::GiveAssist(2, 0),
+            Choice::GiveAssist(2, 1),
+        ]);
+
+        let events = state.choose(Choice::GiveAssist(2, 1)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            GiveAssist(2, 1),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[1].skills.to_vec()).is_equal_to(vec![Rogue]);
+        assert!(state.operators[2].skills.is_empty());
+    }
+
+    #[test]
+    fn choose_flow_stone_desperation() {
+        let mut state = flow_state(&[Stone, Sniper], true);
+        state.firewalls = 0;
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events)
+            .is_equal_to(vec![FirewallDelta(1), ChoiceState(ChoiceState::StoneFlow)]);
+    }
+
+    #[test]
+    fn choose_flow_stone_nobody_to_assist() {
+        let mut state = flow_state(&[Stone], false);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events)
+            .is_equal_to(vec![TurnSummary(summary(0)), ChoiceState(ChooseAction(0))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot give assist, operator 1 no longer has their assist token")]
+    fn perform_give_assist_token_given() {
         let mut state = initial_state_easy();
-        state.hackers.clear();
-        state.perform(Face);
+        state.perform(GiveAssist(1, 0));
+        state.perform(GiveAssist(1, 0));
     }
 
     #[test]
-    #[should_panic(expected = "cannot face, already facing HackerID 3")]
-    fn perform_face_already_facing() {
+    fn choose_flow_rogue() {
+        let mut state = flow_state(&[Rogue, Stone], false);
+        // already acting again, so their skill doesn't apply once the flow is used
+        state.acting_again = true;
+        state.operators[0].backtrace_list = ArrayVec::from_iter([26, 39]);
+        state.operators[1].backtrace_list = ArrayVec::from_iter([16]);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::RogueFlow(0))]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![
+            Choice::DiscardBacktrace(0),
+            Choice::DiscardBacktrace(1),
+        ]);
+
+        let events = state.choose(Choice::DiscardBacktrace(0)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardBacktrace(0),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].backtrace_list.to_vec()).is_equal_to(vec![26]);
+        assert_that(&state.discard.to_vec()).is_equal_to(vec![HackerCard {
+            hacker: 39,
+            face_up: true,
+        }]);
+    }
+
+    #[test]
+    fn choose_flow_rogue_desperation() {
+        let mut state = flow_state(&[Rogue, Stone], true);
+        state.acting_again = true;
+        state.operators[0].backtrace_list = ArrayVec::from_iter([26]);
+        state.operators[1].backtrace_list = ArrayVec::from_iter([16]);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::RogueFlow(1))]);
+
+        let events = state.choose(Choice::DiscardBacktrace(1)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardBacktrace(1),
+            ChoiceState(ChoiceState::RogueDesperationFlow),
+        ]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::UseFlow, Choice::DeclineFlow]);
+
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::RogueFlow(0))]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::DiscardBacktrace(0)]);
+
+        let events = state.choose(Choice::DiscardBacktrace(0)).unwrap();
+        assert_that(&events[..2].to_vec())
+            .is_equal_to(vec![DiscardBacktrace(0), TurnSummary(summary(0))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot discard, operator 0 backtrace list is empty")]
+    fn perform_discard_backtrace_empty() {
         let mut state = initial_state_easy();
-        state.facing = 3;
-        state.perform(Face);
+        state.perform(DiscardBacktrace(0));
     }
 
     #[test]
-    fn perform_idle() {
+    fn choose_flow_biggs() {
+        let mut state = flow_state(&[Biggs, Stone, Charm], false);
+        state.operators[0].backtrace_list = ArrayVec::from_iter([3, 26]);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::BiggsFlow)]);
+        assert_that(&state.valid_choices()).is_equal_to(vec![
+            Choice::TakeBacktrace(0, 0),
+            Choice::TakeBacktrace(0, 1),
+        ]);
+
+        let events = state.choose(Choice::TakeBacktrace(0, 1)).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            TakeBacktrace(0, 1),
+            ChoiceState(ChoiceState::BiggsFlowTarget(0)),
+        ]);
+        assert_that(&state.facing).is_equal_to(26);
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::PassTo(1), Choice::PassTo(2)]);
+
+        let events = state.choose(Choice::PassTo(2)).unwrap();
+        assert_that(&events).is_equal_to(vec![PassFacing(2), ChoiceState(ChoiceState::Face(2))]);
+
+        let events = state.choose(Choice::Secure).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Secure(2),
+            TurnSummary(TurnSummary {
+                secured: ArrayVec::from_iter([26]),
+                ..summary(0)
+            }),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert_that(&state.operators[0].backtrace_list.to_vec()).is_equal_to(vec![3]);
+        assert_that(&state.operators[2].secure_slots).is_equal_to([NO_HACKER, NO_HACKER, 26]);
+    }
+
+    #[test]
+    fn choose_flow_biggs_desperation() {
+        let mut state = flow_state(&[Biggs, Stone], true);
+        state.operators[0].backtrace_list = ArrayVec::from_iter([3, 26]);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events)
+            .is_equal_to(vec![BiggsAgain(true), ChoiceState(ChoiceState::BiggsFlow)]);
+        state
+            .apply_all([Choice::TakeBacktrace(0, 0), Choice::PassTo(1)])
+            .unwrap();
+
+        // asked again once the hacker is placed
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            Backtrace(1),
+            BiggsAgain(false),
+            ChoiceState(ChoiceState::BiggsDesperationFlow),
+        ]);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![ChoiceState(ChoiceState::BiggsFlow)]);
+        state
+            .apply_all([Choice::TakeBacktrace(1, 0), Choice::PassTo(0)])
+            .unwrap();
+
+        // but not a third time
+        let events = state.choose(Choice::Backtrace).unwrap();
+        assert_that(&events[..2].to_vec()).is_equal_to(vec![
+            Backtrace(0),
+            TurnSummary(backtrace_summary(0, &[3, 3])),
+        ]);
+        assert_that(&state.operators[0].backtrace_list.to_vec()).is_equal_to(vec![26, 3]);
+    }
+
+    #[test]
+    fn choose_flow_biggs_alone() {
+        let mut state = flow_state(&[Biggs], false);
+        state.operators[0].backtrace_list = ArrayVec::from_iter([3]);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events)
+            .is_equal_to(vec![TurnSummary(summary(0)), ChoiceState(ChooseAction(0))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot take, operator 0 backtrace list has no index 1")]
+    fn perform_take_backtrace_out_of_range() {
         let mut state = initial_state_easy();
-        state.perform(Idle);
-        assert_that(&state.operators[0].idle).is_true();
+        state.operators[0].backtrace_list = ArrayVec::from_iter([3]);
+        state.perform(TakeBacktrace(0, 1));
+    }
+
+    /// Rich must decide whether to use their flow, with the indicated hackers on the
+    /// hacker stack
+    fn rich_flow_state(hackers: &[HackerID], desperation: bool) -> TableState {
+        let mut state = flow_state(&[Rich, Stone], desperation);
+        state.hackers = HackerDeck::from_iter(hackers.iter().map(|x| HackerCard::new(*x)));
+        state
     }
 
     #[test]
-    #[should_panic(expected = "cannot idle, operator 0 already idle")]
-    fn perform_idle_invalid() {
+    fn choose_flow_rich() {
+        let mut state = rich_flow_state(&[3, 16, 26], false);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            RevealHackers(2),
+            ChoiceState(ChoiceState::RichFlow(2)),
+        ]);
+        let order = |x: [HackerID; 2]| Choice::OrderHackers(ArrayVec::from_iter(x));
+        assert_that(&state.valid_choices()).is_equal_to(vec![order([26, 16]), order([16, 26])]);
+
+        let events = state.choose(order([16, 26])).unwrap();
+        assert_that(&events[..2].to_vec()).is_equal_to(vec![
+            OrderHackers(ArrayVec::from_iter([16, 26])),
+            TurnSummary(summary(0)),
+        ]);
+        let face_up = |hacker| HackerCard {
+            hacker,
+            face_up: true,
+        };
+        assert_that(&state.hackers.to_vec()).is_equal_to(vec![
+            HackerCard::new(3),
+            face_up(26),
+            face_up(16),
+        ]);
+    }
+
+    #[test]
+    fn choose_flow_rich_desperation() {
+        let mut state = rich_flow_state(&[3, 16, 26], true);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            RevealHackers(3),
+            ChoiceState(ChoiceState::RichFlow(3)),
+        ]);
+        assert_that(&state.valid_choices().len()).is_equal_to(6);
+        assert_that(&state.default_choice())
+            .is_equal_to(Some(Choice::OrderHackers(ArrayVec::from_iter([26, 16, 3]))));
+    }
+
+    #[test]
+    fn choose_flow_rich_one_hacker() {
+        let mut state = rich_flow_state(&[3], false);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events[..2].to_vec())
+            .is_equal_to(vec![RevealHackers(1), TurnSummary(summary(0))]);
+        assert!(state.hackers[0].face_up);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot order, HackerID 16 on the hacker stack was not revealed")]
+    fn perform_order_hackers_not_revealed() {
+        let mut state = rich_flow_state(&[3, 16, 26], false);
+        state.perform(RevealHackers(1));
+        state.perform(OrderHackers(ArrayVec::from_iter([16, 26])));
+    }
+
+    #[test]
+    fn perform_shuffle_hackers_face_down() {
+        let mut state = rich_flow_state(&[3, 16, 26], false);
+        state.perform(RevealHackers(2));
+        state.perform(ShuffleHackers);
+        assert!(state.hackers.iter().all(|x| !x.face_up));
+    }
+
+    #[test]
+    fn choose_decline_flow() {
         let mut state = initial_state_easy();
-        state.operators[0].idle = true;
-        state.perform(Idle);
+        state.choice_state = ChoiceState::Flow(0);
+        let events = state.choose(Choice::DeclineFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+    }
+
+    #[test_case(false, 2)]
+    #[test_case(true, 3)]
+    fn choose_flow_sniper(desperation: bool, discarded: u8) {
+        let mut state = initial_state_easy();
+        state.active_operator = 1;
+        state.operators[1].desperation = desperation;
+        state.choice_state = ChoiceState::Flow(1);
+        let hackers = state.hackers.len();
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardHackers(discarded),
+            TurnSummary(summary(1)),
+            ActiveOperator(0),
+            ChoiceState(ChooseAction(0)),
+        ]);
+        assert_that(&state.hackers.len()).is_equal_to(hackers - discarded as usize);
+        assert_that(&state.discard.len()).is_equal_to(discarded as usize);
+    }
+
+    #[test]
+    fn choose_flow_admin_small_breach() {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Admin, Stone]));
+        let mut state = TableState::setup_game(&config.unwrap());
+        state.breach = HackerDeck::from_iter([HackerCard::new(3)]);
+        state.choice_state = ChoiceState::Flow(0);
+        let events = state.choose(Choice::UseFlow).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            DiscardBreach(1),
+            TurnSummary(summary(0)),
+            ActiveOperator(1),
+            ChoiceState(ChooseAction(1)),
+        ]);
+        assert!(state.breach.is_empty());
+        assert_that(&state.discard.to_vec()).is_equal_to(vec![HackerCard {
+            hacker: 3,
+            face_up: true,
+        }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot discard 3 hackers, breach only has 0")]
+    fn perform_discard_breach_too_many() {
+        let mut state = initial_state_easy();
+        state.perform(DiscardBreach(3));
+    }
+
+    #[test]
+    fn valid_choice_assist_once() {
+        let mut state = initial_state_easy();
+        state.perform(Assist(1));
+        assert_that(&state.valid_choices()).is_equal_to(vec![Choice::Idle, Choice::Face]);
+    }
+
+    #[test]
+    fn valid_choice_assist_not_idle() {
+        let mut state = TableState::setup_game(&GameConfig::new(Easy, get_operators(3)).unwrap());
+        state.operators[1].idle = true;
+        assert_that(&state.valid_choices()).is_equal_to(vec![
+            Choice::Idle,
+            Choice::Face,
+            Choice::Assist(2),
+        ]);
+    }
+
+    #[test]
+    fn apply_all() {
+        let mut state = initial_state_easy();
+        let batches = state
+            .apply_all([Choice::Assist(1), Choice::Assist(0)])
+            .unwrap();
+        assert_that(&batches).is_equal_to(vec![
+            vec![
+                Assist(1),
+                TurnSummary(summary(0)),
+                ActiveOperator(1),
+                ChoiceState(ChooseAction(1)),
+            ],
+            vec![
+                Assist(0),
+                TurnSummary(summary(1)),
+                ActiveOperator(0),
+                ChoiceState(ChooseAction(0)),
+            ],
+        ]);
+    }
+
+    #[test]
+    fn apply_all_invalid() {
+        let mut state = initial_state_easy();
+        let result = state.apply_all([Choice::Assist(1), Choice::Assist(0), Choice::Assist(1)]);
+        assert_that(&result).is_equal_to(Err((2, ChoiceError::InvalidChoice(Choice::Assist(1)))));
+        assert_that(&state.choice_state).is_equal_to(ChooseAction(0));
+        assert_that(&state.operators[0].skills.to_vec()).is_equal_to(vec![OPERATORS[1]]);
+    }
+
+    #[test]
+    fn preview_face() {
+        let state = initial_state_easy();
+        let events = state.preview(Choice::Face).unwrap();
+        assert_that(&events).is_equal_to(vec![
+            FaceStarted,
+            FaceRevealed(state.hackers.last().unwrap().hacker),
+            ChoiceState(ChoiceState::Face(0)),
+        ]);
+        assert_that(&state.facing).is_equal_to(NO_HACKER);
+        assert_that(&state.choice_state).is_equal_to(ChooseAction(0));
+    }
+
+    #[test]
+    fn command_face() {
+        let mut state = initial_state_easy();
+        let command = ChoiceCommand::new(&state, Choice::Face);
+        let events = command.dry_run(&state).unwrap();
+        assert_that(&state.facing).is_equal_to(NO_HACKER);
+        assert_that(&command.execute(&mut state).unwrap()).is_equal_to(events);
+        assert_that(&state.choice_state).is_equal_to(ChoiceState::Face(0));
+
+        state.hackers.clear();
+        state.choice_state = ChooseAction(0);
+        let command = ChoiceCommand::new(&state, Choice::Face);
+        assert_that(&command.execute(&mut state)).is_equal_to(Err(ChoiceCommandError::Choice(
+            ChoiceError::InvalidChoice(Choice::Face),
+        )));
     }
 
     #[test]
@@ -1,9 +1,17 @@
-use crate::defs;
 use crate::defs::*;
+use crate::game::coverage::Coverage;
 use arrayvec::ArrayVec;
-use std::collections::HashSet;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
+pub mod annotate;
+pub mod coverage;
+pub mod export;
 pub mod logic;
+#[cfg(feature = "perf-assert")]
+pub mod perf;
+#[cfg(feature = "unstable")]
+pub mod tree;
 
 /// Game state and configuration
 /// TODO: Using ArrayVec here to see if we can keep everything on the stack.
@@ -13,10 +21,18 @@ pub mod logic;
 /// Does not change for the duration of an entire game.
 #[derive(Debug)]
 pub struct GameConfig {
-    /// Operators selected to be in this game in clockwise order.
-    /// Max 7, and all must be unique.
+    /// Operators selected to be in this game in clockwise order, starting with
+    /// whoever takes the first turn. Max 7, and all must be unique.
     operators: ArrayVec<OperatorType, 7>,
     difficulty: Difficulty,
+    /// value 6 hackers which will join the hacker stack in the final round,
+    /// or None to pick them randomly during setup.
+    bosses: Option<Bosses>,
+    /// seed all of the game's randomness is derived from, or None to pick one
+    /// randomly during setup.
+    seed: Option<u64>,
+    /// optional variants to play with
+    options: RuleOptions,
 }
 
 impl GameConfig {
@@ -29,19 +45,109 @@ impl GameConfig {
             return Result::Err(GameConfigError::NoOperators);
         }
 
-        let mut uniq = HashSet::new();
-        for operator in operators.iter() {
-            if uniq.contains(operator) {
+        // checked in order rather than with a set, so the same duplicate is always reported
+        for (i, operator) in operators.iter().enumerate() {
+            if operators[..i].contains(operator) {
                 return Result::Err(GameConfigError::DuplicateOperator(*operator));
             }
-            uniq.insert(operator);
+        }
+
+        let (_, hacker_mult) = logic::difficulty_mod(&difficulty);
+        let needed = operators.len() * hacker_mult;
+        let available = logic::starting_hacker_pool();
+        if needed > available {
+            return Result::Err(GameConfigError::NotEnoughHackers { needed, available });
         }
 
         Result::Ok(GameConfig {
             difficulty,
             operators,
+            bosses: None,
+            seed: None,
+            options: RuleOptions::default(),
         })
     }
+
+    /// Derive all of the game's randomness from the indicated seed, so the same
+    /// config and choices always play out the same way.
+    pub fn with_seed(mut self, seed: u64) -> GameConfig {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Play with the indicated optional variants
+    pub fn with_options(mut self, options: RuleOptions) -> GameConfig {
+        self.options = options;
+        self
+    }
+
+    /// Things about this config which are allowed, but not recommended
+    pub fn report(&self) -> ConfigReport {
+        let mut warnings = Vec::new();
+        if self.operators.len() == 1 {
+            warnings.push(ConfigWarning::SingleOperator);
+        }
+        let (_, hacker_mult) = logic::difficulty_mod(&self.difficulty);
+        let unused = logic::starting_hacker_pool() - self.operators.len() * hacker_mult;
+        if unused < self.operators.len() {
+            warnings.push(ConfigWarning::FewUnusedHackers(unused));
+        }
+        ConfigReport { warnings }
+    }
+
+    /// Use the indicated bosses in the final round rather than picking them randomly.
+    /// There must be exactly as many as the difficulty calls for.
+    pub fn with_bosses(mut self, bosses: Bosses) -> Result<GameConfig, GameConfigError> {
+        if bosses.len() != self.difficulty.boss_count() {
+            return Result::Err(GameConfigError::BossCount(bosses.len()));
+        }
+        for (i, boss) in bosses.iter().enumerate() {
+            if *boss as usize >= HACKERS.len() || hacker(*boss).value() != 6 {
+                return Result::Err(GameConfigError::NotABoss(*boss));
+            }
+            if bosses[..i].contains(boss) {
+                return Result::Err(GameConfigError::DuplicateBoss(*boss));
+            }
+        }
+        self.bosses = Some(bosses);
+        Result::Ok(self)
+    }
+}
+
+/// Optional variants of the rules (house rules). All are off by default, which plays
+/// the game as written.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct RuleOptions {
+    /// Instead of Face, Assist or Idle, an operator can spend their turn repairing a
+    /// webservice or database which was taken down, by discarding the hacker secured in
+    /// their slot of the matching symbol.
+    pub repair: bool,
+    /// Hackers are turned face down as they are placed on the breach, even if they were
+    /// revealed on the hacker stack, so operators can't see what it will do until it is
+    /// resolved.
+    pub facedown_breach: bool,
+    /// Facing stops once the hacker is drawn, leaving it face down until the operator
+    /// makes the Reveal choice, so clients can animate the draw before they learn which
+    /// hacker it is. Doesn't change how the game plays out.
+    pub delay_reveal: bool,
+    /// How choices are made on behalf of operators who fail to decide in time
+    pub default_choice: DefaultChoicePolicy,
+}
+
+/// How TableState::default_choice resolves each choice state. Off by default, which
+/// picks whichever choice is least disruptive to the table. Choice states not listed
+/// always resolve the same way.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct DefaultChoicePolicy {
+    /// ChooseAction: Face if there are hackers left, instead of Idle
+    pub face: bool,
+    /// Skill: use the first applicable skill instead of declining
+    pub use_skill: bool,
+    /// Flow: use the Flow instead of declining it
+    pub use_flow: bool,
+    /// DiscardLeft: discard the highest valued secured hacker instead of the lowest, which
+    /// is the easiest to secure again
+    pub discard_highest: bool,
 }
 
 #[derive(Debug)]
@@ -50,6 +156,36 @@ pub enum GameConfigError {
     DuplicateOperator(OperatorType),
     /// no operators provided
     NoOperators,
+    /// operator count and difficulty call for more hackers than can start in the hacker stack
+    NotEnoughHackers { needed: usize, available: usize },
+    /// wrong number of bosses for the difficulty
+    BossCount(usize),
+    /// hacker is not a value 6 boss
+    NotABoss(HackerID),
+    /// duplicate boss in list
+    DuplicateBoss(HackerID),
+}
+
+/// Result of checking a GameConfig for setups which are not recommended, so lobbies
+/// can warn about them.
+#[derive(Debug)]
+pub struct ConfigReport {
+    pub warnings: Vec<ConfigWarning>,
+}
+
+impl ConfigReport {
+    pub fn is_recommended(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigWarning {
+    /// nobody to assist, so the game plays without that action at all
+    SingleOperator,
+    /// hacker stack uses nearly every hacker available, so every game plays out
+    /// with nearly the same cards. Indicates how many will be left out.
+    FewUnusedHackers(usize),
 }
 
 #[derive(Debug)]
@@ -60,13 +196,54 @@ pub enum Difficulty {
     Heroic,
 }
 
+impl Difficulty {
+    /// How many bosses join the hacker stack in the final round
+    pub fn boss_count(&self) -> usize {
+        match self {
+            Difficulty::Heroic => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Seeds of the independent random streams used by a game. Each domain of randomness
+/// gets its own stream, so that e.g. changing how bots decide never changes which
+/// hackers get shuffled.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct StreamSeeds {
+    /// shuffling the hacker stack and picking bosses
+    pub deck: u64,
+    /// picking hackers to revive from the discard
+    pub revive: u64,
+    /// not used by the engine - for bots to seed their own decisions from
+    pub bot: u64,
+}
+
+impl StreamSeeds {
+    /// Derives the seed of every stream from a single game seed
+    pub fn from_seed(seed: u64) -> StreamSeeds {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        StreamSeeds {
+            deck: rng.gen(),
+            revive: rng.gen(),
+            bot: rng.gen(),
+        }
+    }
+}
+
+/// value 6 hackers chosen for the final round
+pub type Bosses = ArrayVec<HackerID, 2>;
+
 /// Entire state of an ongoing game. This + a GameConfig should contain EVERYTHING needed
 /// to fully describe a state of the game (i.e., a snapshot of this would allow
 /// saving / resuming the game). This should generally not be mutated directly,
 /// but should instead be mutated using the `perform` method.
+#[derive(Clone)]
 pub struct TableState {
     /// amount of firewalls still standing
     firewalls: u8,
+    /// amount of firewalls at the start of the game, which can never be exceeded
+    max_firewalls: u8,
     /// remaining databases: rest, firewall, discard
     databases: [bool; 3],
     /// remaining webservices: compromise, compromise, burnout, burnout, compromise webservice, database
@@ -77,6 +254,8 @@ pub struct TableState {
     breach: HackerDeck,
     /// discarded hackers
     discard: HackerDeck,
+    /// bosses which will join the hacker stack in the final round, fixed at setup
+    bosses: Bosses,
     /// round 0, 1, or 2
     round: u8,
     /// Card currently being faced by active_operator, NO_HACKER if
@@ -87,6 +266,36 @@ pub struct TableState {
     operators: ArrayVec<OperatorState, 7>,
     /// current decision that needs to be made by a operator
     choice_state: ChoiceState,
+    /// seeds the random streams below were created from
+    seeds: StreamSeeds,
+    /// stream for shuffling the hacker stack
+    deck_rng: ChaCha8Rng,
+    /// stream for picking hackers to revive from the discard, and where they go in the
+    /// hacker stack
+    revive_rng: ChaCha8Rng,
+    /// optional variants being played with
+    options: RuleOptions,
+    /// how the game ended, None while it is still being played
+    result: Option<GameResult>,
+    /// whether the active operator is taking a second action this turn, with Rogue's skill
+    acting_again: bool,
+    /// operator the hacker being faced was passed to with Biggs' or Charm's skill, or
+    /// given to with Biggs' flow, None if the active operator is facing it
+    passed_to: Option<OperatorID>,
+    /// whether Biggs' desperation flow can be used a second time once the hacker it gave
+    /// away has been placed
+    biggs_again: bool,
+    /// choices made so far this game, so a ChoiceCommand can tell the decision it was
+    /// made for from a later one in the same choice state
+    decisions: u32,
+    /// what has happened so far in the current turn, emitted as TableEvent::TurnSummary
+    /// once it ends
+    turn_summary: TurnSummary,
+    /// rules exercised so far, if being counted. Not part of what is on the table.
+    coverage: Option<Coverage>,
+    /// latency budget for choose(), and timings of the choice being made
+    #[cfg(feature = "perf-assert")]
+    perf: perf::Perf,
 }
 
 impl TableState {
@@ -120,10 +329,13 @@ impl HackerCard {
     }
 }
 
+#[derive(Clone)]
 pub struct OperatorState {
+    /// which operator this is, regardless of which skills they currently hold
+    operator: OperatorType,
     /// hackers on left side of the operator board,
     /// in the Secure slots.
-    /// index in array: index in defs::SYMBOLS
+    /// index in array: Symbol::secure_slot of the symbol secured there
     /// value: hacker placed there, or defs::NO_HACKER
     secure_slots: [HackerID; 3],
     /// hackers on right side of operator board - backtrace list - end of array = bottom (i.e. most recently placed)
@@ -135,6 +347,13 @@ pub struct OperatorState {
     desperation: bool,
     /// whether they are idling for the remainder of the round
     idle: bool,
+    /// whether a penalty is preventing them from securing, until they idle
+    no_secure: bool,
+    /// whether a penalty is preventing them from giving their assist token, until they idle
+    no_give_assist: bool,
+    /// whether a penalty is preventing them from using any skill, including those of
+    /// assist tokens they hold, until they idle
+    no_talent: bool,
     /// which skills the operator currently has, including their own + any assist
     skills: ArrayVec<OperatorType, 7>,
 }
@@ -143,16 +362,109 @@ impl OperatorState {
     /// New operator in initial state they should be in at start of a game
     pub fn new(operator: &OperatorType) -> OperatorState {
         return OperatorState {
+            operator: *operator,
             secure_slots: [NO_HACKER; 3],
             backtrace_list: ArrayVec::new(),
             burnout: false,
             desperation: false,
             idle: false,
+            no_secure: false,
+            no_give_assist: false,
+            no_talent: false,
             skills: ArrayVec::from_iter([*operator]),
         };
     }
 }
 
+/// Status of a single operator derived from the table state, so every frontend
+/// shows the same status badges.
+#[derive(PartialEq, Debug)]
+pub struct OperatorSummary {
+    pub operator: OperatorType,
+    pub idle: bool,
+    pub burnout: bool,
+    pub desperation: bool,
+    /// total value of the hackers in their backtrace list
+    pub backtrace_total: u8,
+    /// total backtrace value they can handle before being overwhelmed, which is longer
+    /// in desperation mode
+    pub track: u8,
+    /// backtrace value they can still take before being overwhelmed
+    pub remaining_track: u8,
+    /// whether their backtrace list has reached exactly their track, letting them use their flow
+    pub flow_available: bool,
+    /// whether they still hold their own assist token and no penalty prevents giving it,
+    /// and so can assist
+    pub can_assist: bool,
+    /// whether a penalty is preventing them from securing
+    pub no_secure: bool,
+    /// whether a penalty is preventing them from giving their assist token
+    pub no_give_assist: bool,
+    /// whether a penalty is preventing them from using any skill
+    pub no_talent: bool,
+    /// values of the hackers whose penalties they currently ignore, due to the skills
+    /// they hold
+    pub ignored_penalty_values: ArrayVec<u8, 6>,
+    /// assist tokens received from other operators
+    pub assists: ArrayVec<OperatorType, 6>,
+}
+
+/// How a game ended
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    /// operators survived the end of the third round
+    Win,
+    Loss(LossReason),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LossReason {
+    /// every webservice was taken down
+    Webservices,
+    /// every database was taken down
+    Databases,
+    /// indicated operator's backtrace list exceeded their track
+    Overwhelmed(OperatorID),
+    /// indicated operator suffered Burnout while they already had a burnout token
+    BurnedOut(OperatorID),
+}
+
+/// Recap of a single completed turn, so logs and UIs can show what happened without
+/// re-deriving it from the individual events.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct TurnSummary {
+    /// operator whose turn it was
+    pub operator: OperatorID,
+    /// hackers faced, in order
+    pub faced: ArrayVec<HackerID, 13>,
+    /// hackers secured, by any operator
+    pub secured: ArrayVec<HackerID, 13>,
+    /// hackers added to a backtrace list, by any operator, including those drawn by penalties
+    pub backtraced: ArrayVec<HackerID, 13>,
+    /// penalties suffered, in order, excluding NoPenalty
+    pub penalties: ArrayVec<Penalty, 13>,
+    /// change in the number of firewalls
+    pub firewall_delta: i8,
+    /// change in the number of webservices still up
+    pub webservice_delta: i8,
+    /// change in the number of databases still up
+    pub database_delta: i8,
+}
+
+/// What the end of the round will do, as far as the operators can see. Hackers in the
+/// breach are face down unless they were revealed on the hacker stack before being
+/// breached. With RuleOptions.facedown_breach, the whole breach is face down.
+#[derive(PartialEq, Debug)]
+pub struct BreachThreat {
+    /// face down hackers in the breach, which could turn out to be anything
+    pub face_down: u8,
+    /// compromises the face up viruses in the breach will cause
+    pub compromises: u8,
+    /// count of empty secure slots by symbol, index in defs::SYMBOLS - each costs a
+    /// compromise, webservice or database unless it is filled before the round ends
+    pub empty_slots: [u8; 4],
+}
+
 /// Discrete states of the game where player input is required. Each state has associated actions
 /// which can be performed by players (via their operators). All states
 /// must represent situations where a player has some choice to make
@@ -165,41 +477,130 @@ impl OperatorState {
 /// Note we have active_operator in the game state, but some of these enums
 /// still have a OperatorID - this is because sometimes choices need to be
 /// made by operators other than the active operator.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Hash)]
 pub enum ChoiceState {
     /// Specific operator must decide whether to use their Flow or not
     Flow(OperatorID),
     /// Charm (desperation mode) must choose who to heal with their flow
     CharmDesperationFlow,
-    /// Biggs must choose whose backtrace line to take
-    /// a card from and who should receive it. The choice of
-    /// where to place it will be a separate ChoiceState (Face or Skill if applicable)
+    /// Stone must choose which operator gives their assist token to which other operator
+    StoneFlow,
+    /// Rogue must choose whose last backtraced hacker to discard, with the indicated
+    /// number of further uses of their flow allowed after this one
+    RogueFlow(u8),
+    /// Rogue (desperation mode) must choose whether to use their flow a second time
+    RogueDesperationFlow,
+    /// Biggs must choose a hacker to take from any operator's backtrace list. Who
+    /// receives it is a separate BiggsFlowTarget choice, and where they place it a
+    /// separate Face choice.
     BiggsFlow,
+    /// Biggs must choose who receives the hacker taken from the indicated operator's
+    /// backtrace list - anyone other than that operator
+    BiggsFlowTarget(OperatorID),
     /// Biggs must choost whether to use their flow a second time
     BiggsDesperationFlow,
+    /// Rich must choose the order of the indicated number of hackers they turned face up
+    /// on top of the hacker stack with their flow
+    RichFlow(u8),
     /// Indicated operator must choose whether to place card to left or right
     Face(OperatorID),
-    /// Indicated operator must choose whether to use one of their applicable skills.
+    /// Indicated operator must choose whether to use one of their applicable skills - on the
+    /// hacker they are facing, or to act again once their action is resolved.
     Skill(OperatorID),
     /// Indicated operator must choose a card to discard from the left of their board
     DiscardLeft(OperatorID),
+    /// Indicated operator must choose which neighbor to pass the faced hacker to (Biggs'
+    /// and Charm's skill)
+    Pass(OperatorID),
     /// Indicated operator must choose to Face, Assist, or Idle
     ChooseAction(OperatorID),
+    /// Indicated operator has drawn the hacker they are facing and must turn it face up
+    /// (RuleOptions.delay_reveal). They may then use a skill on it unless false.
+    Reveal(OperatorID, bool),
+    /// Indicated operator must choose which webservice a compromise takes down, with the
+    /// indicated number of compromises still to resolve after this one
+    Webservice(OperatorID, u8),
 
     /// Game is over, only action is to quit or start a new one.
     GameOver,
 }
 
 /// Indicates a player's chosen action
-#[derive(PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum Choice {
     /// draw and face next hacker from the hacker deck.
     Face,
+    /// turn the hacker being faced face up (RuleOptions.delay_reveal)
+    Reveal,
     /// Give assist token to another operator
     Assist(OperatorID),
     /// Do nothing for he remainder of the round (also no longer suffer the penalty of the
     /// last raider in the backtrace list)
     Idle,
+    /// Place the faced hacker in the secure slot matching its symbol, on the left of the board
+    Secure,
+    /// Place the faced hacker at the end of the backtrace list, on the right of the board
+    Backtrace,
+    /// Use the indicated skill, which must be one the operator currently holds
+    UseSkill(OperatorType),
+    /// Don't use any of the applicable skills
+    DeclineSkill,
+    /// Discard the hacker in the indicated secure slot, index in OperatorState.secure_slots
+    DiscardSecure(u8),
+    /// Discard the secured Webservice hacker to repair a webservice (RuleOptions.repair)
+    RepairWebservice,
+    /// Discard the secured Database hacker to repair a database (RuleOptions.repair)
+    RepairDatabase,
+    /// Take down the indicated webservice, index in TableState.webservices
+    TakeDownWebservice(u8),
+    /// Use the operator's Flow
+    UseFlow,
+    /// Don't use the operator's Flow
+    DeclineFlow,
+    /// Remove the burnout token of the indicated operator (Charm's desperation flow)
+    RemoveBurnout(OperatorID),
+    /// First operator gives their assist token to the second (Stone's flow)
+    GiveAssist(OperatorID, OperatorID),
+    /// Discard the last hacker in the indicated operator's backtrace list (Rogue's flow)
+    DiscardBacktrace(OperatorID),
+    /// Take the hacker at the indicated index in the indicated operator's backtrace list
+    /// (Biggs' flow)
+    TakeBacktrace(OperatorID, u8),
+    /// Put the hackers turned face up on top of the hacker stack in the indicated order,
+    /// top first (Rich's flow)
+    OrderHackers(ArrayVec<HackerID, 3>),
+    /// Pass the faced hacker to the indicated operator, who must then face it - a neighbor
+    /// with Biggs' or Charm's skill, or anyone other than who it was taken from with
+    /// Biggs' flow
+    PassTo(OperatorID),
+}
+
+/// A choice bound to the decision it was made for, so it can be validated,
+/// previewed and applied later without silently applying to a table which has
+/// since moved on to a different decision - even one in the same choice state.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChoiceCommand {
+    choice_state: ChoiceState,
+    /// TableState.decisions when the command was made
+    decision: u32,
+    choice: Choice,
+}
+
+/// Events emitted while processing a single choice
+pub type EventBatch = Vec<TableEvent>;
+
+#[derive(Debug, PartialEq)]
+pub enum ChoiceError {
+    /// choice is not one of the valid choices in the current choice state
+    InvalidChoice(Choice),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChoiceCommandError {
+    /// table has moved on from the decision the command was made for
+    StaleChoiceState,
+    /// choice could not be made in the choice state the command was made in
+    Choice(ChoiceError),
 }
 
 /// All events which occurred on the table during
@@ -208,7 +609,11 @@ pub enum Choice {
 /// is emitted. This is also the primary way the table state is actually mutated -
 /// generally tablestate should not be updated directly, but should instead be mutated
 /// using the `perform` method.
-#[derive(PartialEq, Debug)]
+///
+/// Applying the events returned by `choose`, in order, to a copy of the state from before
+/// the choice must produce the same state as `choose` did - this is what allows replay,
+/// undo and syncing clients to share a single code path.
+#[derive(Clone, PartialEq, Debug)]
 pub enum TableEvent {
     /// firewall was added or removed - delta from previous value
     /// of TableState.firewalls
@@ -217,19 +622,126 @@ pub enum TableEvent {
     DatabaseRemove(u8),
     /// Webservice was removed, index of the WS in TableState.webservices
     WebserviceRemove(u8),
-    /// top card from hacker stack revealed to active operator
-    /// (in TableState.facing)
-    Face,
+    /// Database was repaired, index of the DB in TableState.databases
+    DatabaseRepair(u8),
+    /// Webservice was repaired, index of the WS in TableState.webservices
+    WebserviceRepair(u8),
+    /// top card from hacker stack drawn by the active operator (in TableState.facing).
+    /// Doesn't include which hacker it is, so clients can animate the draw before
+    /// the FaceRevealed which follows - in the same batch, or once the Reveal choice is
+    /// made with RuleOptions.delay_reveal.
+    FaceStarted,
+    /// hacker drawn by the preceding FaceStarted turned out to be the indicated hacker
+    FaceRevealed(HackerID),
     /// assist token given from active operator to specified operator
     /// as seen in TableState.operators[].skills
     Assist(OperatorID),
-    /// active operator now idle for remainder of round, as seen
+    /// indicated operator now idle for remainder of round, as seen
     /// in TableState.operators[].idle
-    Idle,
+    Idle(OperatorID),
     /// active operator changed to specified OperatorId
     ActiveOperator(OperatorID),
     /// choice state was changed to indicated choice state
     ChoiceState(ChoiceState),
+    /// hacker in TableState.facing was placed in the indicated operator's
+    /// secure slot matching its symbol
+    Secure(OperatorID),
+    /// active operator takes a second action this turn, as seen in TableState.acting_again
+    ActAgain,
+    /// hacker in TableState.facing was moved to the discard pile instead of being placed
+    DiscardFacing,
+    /// hacker in TableState.facing was passed to the indicated operator, who must place it,
+    /// as seen in TableState.passed_to
+    PassFacing(OperatorID),
+    /// hacker in TableState.facing was put face down on the bottom of the hacker stack
+    /// instead of being placed (Rich's skill)
+    BottomDeckFacing,
+    /// hacker in TableState.facing was placed at the end of the indicated
+    /// operator's backtrace list
+    Backtrace(OperatorID),
+    /// top card from hacker stack was placed at the end of the indicated operator's
+    /// backtrace list
+    DrawToBacktrace(OperatorID),
+    /// top card from hacker stack was placed on the breach, staying face up if it was
+    /// revealed unless RuleOptions.facedown_breach is on
+    Breach,
+    /// indicated operator can no longer secure, as seen in TableState.operators[].no_secure
+    NoSecure(OperatorID),
+    /// indicated operator can no longer give their assist token, as seen in
+    /// TableState.operators[].no_give_assist
+    NoGiveAssist(OperatorID),
+    /// indicated operator can no longer use any skill, as seen in
+    /// TableState.operators[].no_talent
+    NoTalent(OperatorID),
+    /// every status preventing the indicated operator from acting was removed
+    ClearStatuses(OperatorID),
+    /// hacker in the indicated operator's secure slot (index in OperatorState.secure_slots)
+    /// was moved to the discard pile
+    DiscardSecure(OperatorID, u8),
+    /// indicated hacker was moved from the discard pile into the indicated position in the
+    /// hacker stack (index in TableState.hackers), face down, both picked with
+    /// TableState.revive_rng. The discard pile is face up, so which hacker returned is
+    /// visible, but clients should not show where it went.
+    HackerRevived(HackerID, u8),
+    /// indicated operator ignored the penalty of the hacker just added to their backtrace
+    /// list, due to the indicated skill they hold
+    PenaltyIgnored(OperatorID, OperatorType),
+    /// penalty suffered by the indicated operator called for the first number of hackers
+    /// from the hacker stack, but it ran out after the second number were drawn
+    ShortDraw(OperatorID, u8, u8),
+    /// indicated operator had no secured hacker, so their DiscardSecure penalty had
+    /// no effect
+    NothingToDiscard(OperatorID),
+    /// indicated number of hackers were moved from the top of the hacker stack to the
+    /// discard pile
+    DiscardHackers(u8),
+    /// indicated number of hackers were moved from the top of the breach to the
+    /// discard pile
+    DiscardBreach(u8),
+    /// first operator's assist token was given to the second operator, as seen in
+    /// TableState.operators[].skills
+    GiveAssist(OperatorID, OperatorID),
+    /// last hacker in the indicated operator's backtrace list was moved to the discard pile
+    DiscardBacktrace(OperatorID),
+    /// hacker at the indicated index in the indicated operator's backtrace list was taken
+    /// out, and is now being faced (TableState.facing) until it is passed on
+    TakeBacktrace(OperatorID, u8),
+    /// whether Biggs' desperation flow can be used a second time was set to the indicated
+    /// value, as seen in TableState.biggs_again
+    BiggsAgain(bool),
+    /// indicated number of hackers on top of the hacker stack were turned face up
+    RevealHackers(u8),
+    /// face up hackers on top of the hacker stack were put in the indicated order, top
+    /// first
+    OrderHackers(ArrayVec<HackerID, 3>),
+    /// indicated operator received a burnout token, as seen in TableState.operators[].burnout
+    Burnout(OperatorID),
+    /// burnout token of the indicated operator was removed
+    BurnoutRemoved(OperatorID),
+    /// indicated operator entered desperation mode, as seen in TableState.operators[].desperation
+    Desperation(OperatorID),
+    /// every assist token was returned to its owner
+    ReturnAssists,
+    /// hackers secured by and backtraced by the indicated operator were moved to the
+    /// discard pile
+    DiscardBoard(OperatorID),
+    /// round ended and the next round began (TableState.round), with no operator idle
+    RoundEnd,
+    /// every hacker in the breach was moved onto the hacker stack, face down
+    BreachToStack,
+    /// indicated number of value 5 lieutenants, picked randomly, were added to the
+    /// hacker stack face down. Which lieutenants they are is deliberately left out, as
+    /// the operators don't see them until they are drawn - clients animate the count of
+    /// face down cards.
+    AddLieutenants(u8),
+    /// TableState.bosses were added to the hacker stack face down
+    AddBosses,
+    /// hacker stack was shuffled, turning every hacker in it face down
+    ShuffleHackers,
+    /// turn of TurnSummary.operator ended, having done what the summary describes
+    TurnSummary(TurnSummary),
+    /// game ended with the indicated result (TableState.result), choice state is now GameOver
+    GameOver(GameResult),
     // TODO: Add more as needed
 }
 
@@ -262,11 +774,64 @@ mod tests {
         validate_unique_operators(3, vec![Biggs, Sniper, Charm, Charm]);
     }
 
-    fn validate_unique_operators(dupe_idx: u8, operators: Vec<OperatorType>) {
+    #[test]
+    fn report_recommended() {
+        let config = GameConfig::new(Difficulty::Normal, ArrayVec::from_iter([Biggs, Charm]));
+        assert!(config.unwrap().report().is_recommended());
+    }
+
+    #[test]
+    fn report_warnings() {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Biggs])).unwrap();
+        assert_eq!(
+            config.report().warnings,
+            vec![ConfigWarning::SingleOperator]
+        );
+
+        let operators = [Stone, Sniper, Rogue, Biggs, Rich, Charm, Admin];
+        let config = GameConfig::new(Difficulty::Heroic, ArrayVec::from_iter(operators)).unwrap();
+        assert_eq!(
+            config.report().warnings,
+            vec![ConfigWarning::FewUnusedHackers(3)]
+        );
+    }
+
+    #[test]
+    fn with_bosses() {
+        let config = GameConfig::new(Difficulty::Heroic, ArrayVec::from_iter([Biggs]))
+            .unwrap()
+            .with_bosses(ArrayVec::from_iter([61, 65]))
+            .unwrap();
+        assert_eq!(config.bosses, Some(ArrayVec::from_iter([61, 65])));
+    }
+
+    #[test]
+    fn with_bosses_invalid() {
+        let config = || GameConfig::new(Difficulty::Heroic, ArrayVec::from_iter([Biggs])).unwrap();
+        assert!(matches!(
+            config().with_bosses(ArrayVec::from_iter([61])),
+            Err(GameConfigError::BossCount(1))
+        ));
+        assert!(matches!(
+            config().with_bosses(ArrayVec::from_iter([61, 3])),
+            Err(GameConfigError::NotABoss(3))
+        ));
+        assert!(matches!(
+            config().with_bosses(ArrayVec::from_iter([61, 66])),
+            Err(GameConfigError::NotABoss(66))
+        ));
+        assert!(matches!(
+            config().with_bosses(ArrayVec::from_iter([62, 62])),
+            Err(GameConfigError::DuplicateBoss(62))
+        ));
+    }
+
+    fn validate_unique_operators(dupe_idx: usize, operators: Vec<OperatorType>) {
+        let dupe = operators[dupe_idx];
         let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter(operators)).unwrap_err();
         assert!(matches!(
             config,
-            GameConfigError::DuplicateOperator(dupe_idx)
+            GameConfigError::DuplicateOperator(x) if x == dupe
         ));
     }
 }
@@ -0,0 +1,177 @@
+/// Latency budget for choose() (perf-assert feature), so accidentally quadratic work
+/// added while implementing rules is caught by the first debug run which hits it.
+use super::{Choice, TableEvent, TableState};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How many of the slowest events to include in the breakdown
+const SLOWEST_EVENTS: usize = 5;
+
+/// How long a single choose() may take, and what happens when one takes longer
+#[derive(Copy, Clone, Debug)]
+pub struct ChoiceBudget {
+    pub budget: Duration,
+    /// called with the breakdown of each choice over the budget. If None, a debug
+    /// assertion fails with the breakdown instead, and nothing happens in release builds.
+    pub on_exceeded: Option<fn(&ChoiceTiming)>,
+}
+
+/// Where the time went during a choice which went over its budget
+#[derive(Clone, Debug)]
+pub struct ChoiceTiming {
+    pub choice: Choice,
+    pub budget: Duration,
+    pub total: Duration,
+    /// time spent checking the choice is valid
+    pub validate: Duration,
+    /// time spent performing each event, in the order they were emitted
+    pub events: Vec<(TableEvent, Duration)>,
+}
+
+impl ChoiceTiming {
+    /// time spent deciding which events to emit, rather than validating or performing
+    pub fn deciding(&self) -> Duration {
+        let performing: Duration = self.events.iter().map(|(_, x)| *x).sum();
+        self.total.saturating_sub(self.validate + performing)
+    }
+}
+
+impl fmt::Display for ChoiceTiming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "choose({:?}) took {}µs, over the budget of {}µs: {}µs validating, {}µs deciding, {} events",
+            self.choice,
+            self.total.as_micros(),
+            self.budget.as_micros(),
+            self.validate.as_micros(),
+            self.deciding().as_micros(),
+            self.events.len()
+        )?;
+        let mut slowest: Vec<_> = self.events.iter().collect();
+        slowest.sort_by_key(|(_, x)| std::cmp::Reverse(*x));
+        for (event, time) in slowest.into_iter().take(SLOWEST_EVENTS) {
+            write!(f, "\n  {}µs {:?}", time.as_micros(), event)?;
+        }
+        Ok(())
+    }
+}
+
+/// Budget, and where the time went during the choice currently being made
+#[derive(Clone, Debug, Default)]
+pub(super) struct Perf {
+    /// None until set, in which case choices aren't checked
+    budget: Option<ChoiceBudget>,
+    validate: Duration,
+    events: Vec<(TableEvent, Duration)>,
+}
+
+impl Perf {
+    /// Starts timing a new choice
+    pub(super) fn start(&mut self) -> Instant {
+        self.validate = Duration::ZERO;
+        self.events.clear();
+        Instant::now()
+    }
+
+    pub(super) fn validated(&mut self, elapsed: Duration) {
+        self.validate = elapsed;
+    }
+
+    pub(super) fn performed(&mut self, event: &TableEvent, elapsed: Duration) {
+        self.events.push((event.clone(), elapsed));
+    }
+
+    /// Reports the choice if it took longer than the budget
+    pub(super) fn check(&mut self, choice: &Choice, elapsed: Duration) {
+        let budget = match self.budget {
+            Some(x) if elapsed > x.budget => x,
+            _ => return,
+        };
+        let timing = ChoiceTiming {
+            choice: choice.clone(),
+            budget: budget.budget,
+            total: elapsed,
+            validate: self.validate,
+            events: std::mem::take(&mut self.events),
+        };
+        match budget.on_exceeded {
+            Some(on_exceeded) => on_exceeded(&timing),
+            None => debug_assert!(false, "{}", timing),
+        }
+    }
+}
+
+impl TableState {
+    /// Sets how long a single choose() may take. No choices are checked until this is
+    /// called, as what is reasonable depends on the machine.
+    pub fn set_choice_budget(&mut self, budget: ChoiceBudget) {
+        self.perf.budget = Some(budget);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Difficulty, GameConfig};
+    use super::*;
+    use crate::defs::OperatorType::*;
+    use arrayvec::ArrayVec;
+    use spectral::prelude::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static EXCEEDED: RefCell<Vec<ChoiceTiming>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn record(timing: &ChoiceTiming) {
+        EXCEEDED.with(|x| x.borrow_mut().push(timing.clone()));
+    }
+
+    fn state() -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Stone, Sniper]));
+        TableState::setup_game(&config.unwrap())
+    }
+
+    #[test]
+    fn unchecked_without_budget() {
+        let mut state = state();
+        state.choose(Choice::Face).unwrap();
+    }
+
+    #[test]
+    fn within_budget() {
+        let mut state = state();
+        state.set_choice_budget(ChoiceBudget {
+            budget: Duration::from_secs(60),
+            on_exceeded: Some(record),
+        });
+        state.choose(Choice::Face).unwrap();
+        EXCEEDED.with(|x| assert!(x.borrow().is_empty()));
+    }
+
+    #[test]
+    fn over_budget_reported() {
+        let mut state = state();
+        state.set_choice_budget(ChoiceBudget {
+            budget: Duration::ZERO,
+            on_exceeded: Some(record),
+        });
+        state.choose(Choice::Face).unwrap();
+        let timing = EXCEEDED.with(|x| x.borrow_mut().pop().unwrap());
+        assert_that(&timing.choice).is_equal_to(Choice::Face);
+        assert_that(&timing.events.len()).is_equal_to(3);
+        assert_that(&timing.to_string().lines().count()).is_equal_to(1 + 3);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "over the budget of 0µs")]
+    fn over_budget_asserts() {
+        let mut state = state();
+        state.set_choice_budget(ChoiceBudget {
+            budget: Duration::ZERO,
+            on_exceeded: None,
+        });
+        state.choose(Choice::Face).unwrap();
+    }
+}
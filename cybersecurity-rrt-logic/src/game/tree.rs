@@ -0,0 +1,257 @@
+//! Enumeration of the choices reachable from a table state, for move explorers
+//! and for debugging valid choice generation.
+use super::{Choice, ChoiceState, EventBatch, HackerDeck, TableEvent, TableState};
+use crate::defs::NO_HACKER;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// A choice which can be made, and what it leads to
+#[derive(Debug)]
+pub struct ChoiceNode {
+    pub choice: Choice,
+    /// choice state the table is in after making the choice, or just before the draw
+    /// for Draw nodes
+    pub choice_state: ChoiceState,
+    /// hash of the visible table state after making the choice (or just before the draw
+    /// for Draw nodes) - equal for choices
+    /// which lead to the same visible state. Only comparable within a single build, as
+    /// the hash function may change between Rust releases, so it must not be persisted.
+    pub state_hash: u64,
+    /// events emitted while making the choice, stopping before the draw for Draw nodes
+    pub events: EventBatch,
+    pub kind: NodeKind,
+    /// choices reachable from the resulting state, empty unless kind is Expanded
+    pub children: Vec<ChoiceNode>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum NodeKind {
+    /// children were enumerated
+    Expanded,
+    /// max depth reached, children were not enumerated
+    Leaf,
+    /// a hacker was drawn or the hacker stack shuffled, so what follows depends on cards
+    /// the operators can't see. The node stops just before the draw and children are not
+    /// enumerated, so the tree doesn't reveal the hacker stack.
+    Draw,
+    /// resulting state was already visited further up this branch
+    Cycle,
+}
+
+/// Tree of all choices reachable from the state, up to depth choices deep
+pub fn choice_tree(state: &TableState, depth: usize) -> Vec<ChoiceNode> {
+    let mut path = vec![state_hash(state)];
+    expand(state, depth, &mut path)
+}
+
+fn expand(state: &TableState, depth: usize, path: &mut Vec<u64>) -> Vec<ChoiceNode> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut nodes = Vec::new();
+    for choice in state.valid_choices_iter() {
+        let mut next = state.clone();
+        let mut events = next
+            .choose(choice.clone())
+            .expect("valid choice was rejected");
+        let drawn = events.iter().position(draws);
+        if let Some(idx) = drawn {
+            events.truncate(idx);
+            next = state.clone();
+            for event in events.iter() {
+                next.perform(event.clone());
+            }
+        }
+        let hash = state_hash(&next);
+        let kind = if drawn.is_some() {
+            NodeKind::Draw
+        } else if path.contains(&hash) {
+            NodeKind::Cycle
+        } else if depth == 1 {
+            NodeKind::Leaf
+        } else {
+            NodeKind::Expanded
+        };
+        let children = if kind == NodeKind::Expanded {
+            path.push(hash);
+            let children = expand(&next, depth - 1, path);
+            path.pop();
+            children
+        } else {
+            Vec::new()
+        };
+        nodes.push(ChoiceNode {
+            choice,
+            choice_state: next.choice_state.clone(),
+            state_hash: hash,
+            events,
+            kind,
+            children,
+        });
+    }
+    nodes
+}
+
+/// Whether the event depends on the order of the hacker stack, which the operators
+/// can't see
+fn draws(event: &TableEvent) -> bool {
+    matches!(
+        event,
+        TableEvent::FaceStarted
+            | TableEvent::DrawToBacktrace(_)
+            | TableEvent::Breach
+            | TableEvent::DiscardHackers(_)
+            | TableEvent::RevealHackers(_)
+            | TableEvent::HackerRevived(_, _)
+            | TableEvent::AddLieutenants(_)
+            | TableEvent::ShuffleHackers
+    )
+}
+
+/// Hash of everything visible on the table - never the order of the hacker stack or
+/// any other face down card
+fn state_hash(state: &TableState) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.round.hash(&mut hasher);
+    state.active_operator.hash(&mut hasher);
+    state.choice_state.hash(&mut hasher);
+    state.acting_again.hash(&mut hasher);
+    state.passed_to.hash(&mut hasher);
+    state.biggs_again.hash(&mut hasher);
+    state.facing.hash(&mut hasher);
+    state.firewalls.hash(&mut hasher);
+    state.databases.hash(&mut hasher);
+    state.webservices.hash(&mut hasher);
+    for deck in [&state.hackers, &state.breach, &state.discard] {
+        hash_deck(deck, &mut hasher);
+    }
+    for operator in state.operators.iter() {
+        operator.operator.hash(&mut hasher);
+        operator.secure_slots.hash(&mut hasher);
+        operator.backtrace_list.hash(&mut hasher);
+        operator.skills.hash(&mut hasher);
+        operator.burnout.hash(&mut hasher);
+        operator.desperation.hash(&mut hasher);
+        operator.idle.hash(&mut hasher);
+        operator.no_secure.hash(&mut hasher);
+        operator.no_give_assist.hash(&mut hasher);
+        operator.no_talent.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Hashes the size of the deck and which of its cards are face up
+fn hash_deck(deck: &HackerDeck, hasher: &mut DefaultHasher) {
+    deck.len().hash(hasher);
+    for card in deck.iter() {
+        let visible = if card.face_up { card.hacker } else { NO_HACKER };
+        visible.hash(hasher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Difficulty, GameConfig};
+    use super::*;
+    use crate::defs::HackerID;
+    use crate::defs::OperatorType::*;
+    use arrayvec::ArrayVec;
+    use spectral::prelude::*;
+
+    fn state() -> TableState {
+        let config = GameConfig::new(Difficulty::Easy, ArrayVec::from_iter([Biggs, Charm, Rich]));
+        TableState::setup_game(&config.unwrap())
+    }
+
+    fn choices(nodes: &[ChoiceNode]) -> Vec<Choice> {
+        nodes.iter().map(|x| x.choice.clone()).collect()
+    }
+
+    #[test]
+    fn top_level_matches_valid_choices() {
+        let state = state();
+        let tree = choice_tree(&state, 1);
+        assert_that(&choices(&tree)).is_equal_to(state.valid_choices());
+        assert!(tree
+            .iter()
+            .all(|x| x.kind == NodeKind::Leaf || x.kind == NodeKind::Draw));
+        assert!(tree.iter().all(|x| x.children.is_empty()));
+    }
+
+    #[test]
+    fn draws_are_not_expanded() {
+        let tree = choice_tree(&state(), 2);
+        let face = tree.iter().find(|x| x.choice == Choice::Face).unwrap();
+        assert_that(&face.kind).is_equal_to(NodeKind::Draw);
+        assert!(face.children.is_empty());
+    }
+
+    #[test]
+    fn round_end_is_not_expanded() {
+        let mut state = state();
+        state.operators[1].idle = true;
+        state.operators[2].idle = true;
+        // nothing missing from the secure slots, so the round end doesn't lose the game
+        for (i, operator) in state.operators.iter_mut().enumerate() {
+            operator.secure_slots = [8 + i as HackerID, 4 + i as HackerID, i as HackerID];
+        }
+        let tree = choice_tree(&state, 2);
+        let idle = tree.iter().find(|x| x.choice == Choice::Idle).unwrap();
+        assert!(idle.events.contains(&TableEvent::ReturnAssists));
+        assert!(!idle.events.iter().any(draws));
+        assert_that(&idle.kind).is_equal_to(NodeKind::Draw);
+        assert!(idle.children.is_empty());
+    }
+
+    #[test]
+    fn expands_to_depth() {
+        let state = state();
+        let tree = choice_tree(&state, 2);
+        let idle = tree.iter().find(|x| x.choice == Choice::Idle).unwrap();
+        assert_that(&idle.kind).is_equal_to(NodeKind::Expanded);
+
+        let mut next = state.clone();
+        next.choose(Choice::Idle).unwrap();
+        assert_that(&choices(&idle.children)).is_equal_to(next.valid_choices());
+        assert!(idle.children.iter().all(|x| x.children.is_empty()));
+    }
+
+    #[test]
+    fn same_state_same_hash() {
+        let state = state();
+        let tree = choice_tree(&state, 2);
+        let idle = tree.iter().find(|x| x.choice == Choice::Idle).unwrap();
+        let assist = tree.iter().find(|x| x.choice == Choice::Assist(1)).unwrap();
+        assert_that(&idle.state_hash).is_not_equal_to(assist.state_hash);
+        assert_that(&choice_tree(&state, 1)[0].state_hash).is_equal_to(tree[0].state_hash);
+    }
+
+    #[test]
+    fn draw_nodes_ignore_hacker_stack_order() {
+        let state = state();
+        let mut reordered = state.clone();
+        reordered.hackers.reverse();
+        let tree = choice_tree(&state, 1);
+        let reordered_tree = choice_tree(&reordered, 1);
+        let face = tree.iter().find(|x| x.choice == Choice::Face).unwrap();
+        let reordered_face = reordered_tree
+            .iter()
+            .find(|x| x.choice == Choice::Face)
+            .unwrap();
+        assert_that(&face.kind).is_equal_to(NodeKind::Draw);
+        assert_that(&face.choice_state).is_equal_to(&state.choice_state);
+        assert_that(&face.events).is_equal_to(&reordered_face.events);
+        assert_that(&face.choice_state).is_equal_to(&reordered_face.choice_state);
+        assert_that(&face.state_hash).is_equal_to(reordered_face.state_hash);
+    }
+
+    #[test]
+    fn hash_ignores_hacker_stack_order() {
+        let state = state();
+        let mut reordered = state.clone();
+        reordered.hackers.reverse();
+        assert_that(&state_hash(&reordered)).is_equal_to(state_hash(&state));
+        reordered.hackers.pop();
+        assert_that(&state_hash(&reordered)).is_not_equal_to(state_hash(&state));
+    }
+}
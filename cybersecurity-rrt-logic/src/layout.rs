@@ -0,0 +1,97 @@
+//! Canonical logical arrangement of the table, so every graphical client lays out the
+//! same game the same way and a replay looks identical wherever it is watched. This only
+//! describes what goes where relative to everything else; sizes and pixel positions are
+//! up to the client.
+
+/// Area of the table holding one kind of component
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Zone {
+    /// firewalls still standing, as a single stack
+    Firewalls,
+    /// webservices in the order they go down, left to right
+    Webservices,
+    /// databases in the order they go down, left to right
+    Databases,
+    /// face down hacker stack
+    HackerStack,
+    Breach,
+    Discard,
+    /// an operator's secure slots, left to right in the order of Symbol::secure_slot
+    SecureSlots(u8),
+    /// an operator's backtraced hackers, left to right in the order they were backtraced
+    Backtrace(u8),
+}
+
+impl Zone {
+    /// Identifier for the zone which is stable across versions, for naming elements in
+    /// rendered output such as SVG ids
+    pub fn id(&self) -> String {
+        match self {
+            Zone::Firewalls => "firewalls".into(),
+            Zone::Webservices => "webservices".into(),
+            Zone::Databases => "databases".into(),
+            Zone::HackerStack => "hacker-stack".into(),
+            Zone::Breach => "breach".into(),
+            Zone::Discard => "discard".into(),
+            Zone::SecureSlots(seat) => format!("seat{}-secure", seat),
+            Zone::Backtrace(seat) => format!("seat{}-backtrace", seat),
+        }
+    }
+}
+
+/// Zones in the middle of the table, in rows from the top of the table, each row from
+/// left to right. Infrastructure is ordered the way damage spills over, firewalls first.
+pub static CENTER_ROWS: [&[Zone]; 2] = [
+    &[Zone::Firewalls, Zone::Webservices, Zone::Databases],
+    &[Zone::HackerStack, Zone::Breach, Zone::Discard],
+];
+
+/// Zones in front of the operator in the seat, from the operator's left to right
+pub fn seat_zones(seat: u8) -> [Zone; 2] {
+    [Zone::SecureSlots(seat), Zone::Backtrace(seat)]
+}
+
+/// Position of a seat around the table in degrees clockwise from the bottom of the
+/// table, where the first operator sits. Seats are spaced evenly and go clockwise in
+/// turn order, so seat i is the operator at index i of GameConfig.operators.
+pub fn seat_angle(seat: u8, operators: u8) -> u16 {
+    assert!(
+        seat < operators,
+        "seat {} out of {} operators",
+        seat,
+        operators
+    );
+    (360 * seat as u32 / operators as u32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spectral::prelude::*;
+    use test_case::test_case;
+
+    #[test_case(1, &[0])]
+    #[test_case(3, &[0, 120, 240])]
+    #[test_case(4, &[0, 90, 180, 270])]
+    #[test_case(7, &[0, 51, 102, 154, 205, 257, 308])]
+    fn seat_angles(operators: u8, expected: &[u16]) {
+        let angles: Vec<u16> = (0..operators).map(|x| seat_angle(x, operators)).collect();
+        assert_that(&angles).is_equal_to(expected.to_vec());
+    }
+
+    #[test]
+    fn ids_unique() {
+        let mut ids: Vec<String> = CENTER_ROWS
+            .iter()
+            .flat_map(|x| x.iter())
+            .map(Zone::id)
+            .collect();
+        for seat in 0..7 {
+            ids.extend(seat_zones(seat).iter().map(Zone::id));
+        }
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_that(&ids.len()).is_equal_to(count);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod defs;
 mod game;
+pub mod layout;
+pub mod prelude;
+pub mod rules;
+#[cfg(feature = "unstable")]
+pub mod unstable;
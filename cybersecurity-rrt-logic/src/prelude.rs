@@ -0,0 +1,18 @@
+//! Stable public surface of the engine: everything a client needs to set up a game,
+//! make choices and follow what happened. These only change along with a bump of the
+//! crate's major version.
+//!
+//! Tooling whose API may change between any two versions (move explorers, rule coverage,
+//! flat state export) lives in `unstable`, behind the `unstable` feature.
+pub use crate::defs::{HackerID, OperatorType, Penalty, Symbol, NO_HACKER};
+pub use crate::game::annotate::{AnnotatedChoice, RuleViolation};
+#[cfg(feature = "perf-assert")]
+pub use crate::game::perf::{ChoiceBudget, ChoiceTiming};
+pub use crate::game::{
+    BreachThreat, Choice, ChoiceCommand, ChoiceCommandError, ChoiceError, ChoiceState,
+    ConfigReport, ConfigWarning, DefaultChoicePolicy, Difficulty, EventBatch, GameConfig,
+    GameConfigError, GameResult, LossReason, OperatorSummary, RuleOptions, StreamSeeds, TableEvent,
+    TableState, TurnSummary,
+};
+pub use crate::layout::{seat_angle, seat_zones, Zone, CENTER_ROWS};
+pub use crate::rules::{check_rules_version, RulesVersionError, RULES_VERSION};
@@ -0,0 +1,416 @@
+//! Structured text of the rules implemented by the engine, for help overlays, and
+//! versioning of those rules so that saves, replays and clients recorded against
+//! different rules fail fast rather than silently desyncing.
+use crate::defs::{self, HackerID, Keyword, OperatorType};
+
+/// Version of the rules implemented by the engine. Must be bumped on any change which
+/// could cause the same choices from the same state to produce different events, with
+/// an entry in CHANGELOG describing it.
+pub const RULES_VERSION: u32 = 1;
+
+/// Older rules versions which produce identical events to RULES_VERSION, so anything
+/// recorded under them can still be used as is.
+static COMPATIBLE_RULES_VERSIONS: [u32; 0] = [];
+
+#[derive(Debug, PartialEq)]
+pub enum RulesVersionError {
+    /// version is newer than the rules this engine implements
+    Newer(u32),
+    /// version is older and its rules differ from the rules this engine implements
+    Incompatible(u32),
+}
+
+/// Checks whether something recorded under the indicated rules version can be used with
+/// this engine.
+pub fn check_rules_version(version: u32) -> Result<(), RulesVersionError> {
+    if version > RULES_VERSION {
+        return Result::Err(RulesVersionError::Newer(version));
+    }
+    if version != RULES_VERSION && !COMPATIBLE_RULES_VERSIONS.contains(&version) {
+        return Result::Err(RulesVersionError::Incompatible(version));
+    }
+    Result::Ok(())
+}
+
+/// A change to how the engine plays out the game
+pub struct RuleChange {
+    version: u32,
+    description: &'static str,
+    hackers: &'static [HackerID],
+    operators: &'static [OperatorType],
+}
+
+impl RuleChange {
+    /// rules version the change was made in
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+    /// hackers which play differently, empty if the change isn't specific to any
+    pub fn hackers(&self) -> &'static [HackerID] {
+        self.hackers
+    }
+    /// operators who play differently, empty if the change isn't specific to any
+    pub fn operators(&self) -> &'static [OperatorType] {
+        self.operators
+    }
+}
+
+/// Every change to the rules, oldest first. Version 1 is the first released version, so
+/// its entries describe the rules as first released rather than changes to older ones.
+pub static CHANGELOG: [RuleChange; 22] = [
+    RuleChange {
+        version: 1,
+        description: "Facing draws the top hacker of the hacker stack, emitting FaceStarted and then FaceRevealed with the hacker. Assist gives the operator's assist token to another operator, and Idle makes the operator idle for the rest of the round. Turns pass clockwise, skipping idle operators.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "A faced hacker is either secured in the slot matching its symbol or placed at the end of the backtrace list.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Operators are asked whether to use a skill which applies to the hacker they face before placing it.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Backtracing a hacker applies its penalty, then offers the Flow to the operator who backtraced it, or the neighbor it was passed to, if their backtrace list adds up to exactly their track length.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Compromise and DoubleCompromise take down a firewall each, or a webservice of the operator's choice once no firewalls are left. Firewalls can number up to the amount the game started with.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Ninja and DoubleNinja place the top hackers of the hacker stack on the breach. They, DrawLeft and DrawRight emit ShortDraw when the hacker stack runs out before they have drawn every hacker they call for.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "NoSecure and NoGiveAssist prevent the operator from securing or giving their assist token until they idle.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "DiscardSecure and DiscardLeft make the operator choose a secured hacker to discard.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "NoSecureAndHackerRevive also returns a random hacker from the discard to a random position in the hacker stack, which HackerRevived names.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Burnout penalties give the operator a burnout token, losing the game if they already have one, and Charm's desperation flow can remove one.",
+        hackers: &[],
+        operators: &[OperatorType::Charm],
+    },
+    RuleChange {
+        version: 1,
+        description: "Once every operator is idle the round ends: missing secured symbols and viruses in the breach damage the infrastructure, burned out operators enter desperation, assist tokens return and boards are discarded.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Between rounds the breach is shuffled back into the hacker stack along with a lieutenant per operator for the second round, or the bosses for the final round.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "The game is lost once all webservices or all databases are down or an operator's backtrace list exceeds their track, and won once the final round ends.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "The Idle event names the operator who idles, as penalties can make an operator other than the active one idle.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Every turn ends with a TurnSummary event recapping it.",
+        hackers: &[],
+        operators: &[],
+    },
+    RuleChange {
+        version: 1,
+        description: "Stone's skill can be used to discard a faced hacker whose value matches one in their backtrace list.",
+        hackers: &[],
+        operators: &[OperatorType::Stone],
+    },
+    RuleChange {
+        version: 1,
+        description: "Sniper, and anyone holding their assist token, ignores the penalties of even valued hackers.",
+        hackers: &[],
+        operators: &[OperatorType::Sniper],
+    },
+    RuleChange {
+        version: 1,
+        description: "Admin, and anyone holding their assist token, ignores the penalties of odd valued hackers.",
+        hackers: &[],
+        operators: &[OperatorType::Admin],
+    },
+    RuleChange {
+        version: 1,
+        description: "Rogue's skill can be used to take a second action once their first action is resolved, unless they idled.",
+        hackers: &[],
+        operators: &[OperatorType::Rogue],
+    },
+    RuleChange {
+        version: 1,
+        description: "Biggs' and Charm's skills can be used to pass a faced hacker of odd or even value respectively to a neighbor, who must then face it.",
+        hackers: &[],
+        operators: &[OperatorType::Biggs, OperatorType::Charm],
+    },
+    RuleChange {
+        version: 1,
+        description: "Rich's skill can be used to put a faced hacker on the bottom of the hacker stack and face the next one instead.",
+        hackers: &[],
+        operators: &[OperatorType::Rich],
+    },
+    RuleChange {
+        version: 1,
+        description: "Stone's flow has any operator give their assist token to another, Rogue's discards the last hacker of a backtrace list, Biggs' gives a hacker from a backtrace list to another operator to place, and Rich's turns the top hackers of the hacker stack face up to reorder them.",
+        hackers: &[],
+        operators: &[OperatorType::Stone, OperatorType::Rogue, OperatorType::Biggs, OperatorType::Rich],
+    },
+];
+
+/// Changes made after the indicated rules version, oldest first - what changed since
+/// something recorded under that version.
+pub fn changes_since(version: u32) -> impl Iterator<Item = &'static RuleChange> {
+    CHANGELOG.iter().filter(move |x| x.version > version)
+}
+
+/// Part of the game a rule applies to
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Phase {
+    Setup,
+    /// an operator's turn
+    Turn,
+    /// processing once every operator is idle
+    RoundEnd,
+    /// checking whether the game is won or lost
+    GameEnd,
+}
+
+/// A single rule as shown in a help overlay
+pub struct Rule {
+    title: &'static str,
+    text: &'static str,
+    phase: Phase,
+    keywords: &'static [Keyword],
+}
+
+impl Rule {
+    pub fn title(&self) -> &'static str {
+        self.title
+    }
+    pub fn text(&self) -> &'static str {
+        self.text
+    }
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+    pub fn keywords(&self) -> &'static [Keyword] {
+        self.keywords
+    }
+}
+
+/// Every rule of the game, in the order they come up during play
+pub static RULES: [Rule; 13] = [
+    Rule {
+        title: "Setup",
+        text: "Each operator starts holding their own assist token. Firewalls and the size of the hacker stack depend on the number of operators and the difficulty. The first operator takes the first turn, then play continues clockwise.",
+        phase: Phase::Setup,
+        keywords: &[Keyword::Firewall, Keyword::HackerStack, Keyword::Assist],
+    },
+    Rule {
+        title: "Taking a turn",
+        text: "On their turn, the active operator must Face, Assist or Idle. Turns pass clockwise, skipping operators who are idle.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Face, Keyword::Assist, Keyword::Idle],
+    },
+    Rule {
+        title: "Face",
+        text: "Draw the top hacker of the hacker stack, then either Secure it or add it to the Backtrace list.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Face, Keyword::HackerStack, Keyword::Secure, Keyword::Backtrace],
+    },
+    Rule {
+        title: "Secure",
+        text: "Place the faced hacker in the empty secure slot matching its symbol. Hackers with no symbol cannot be secured.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Secure],
+    },
+    Rule {
+        title: "Backtrace",
+        text: "Add the faced hacker to the end of the backtrace list and suffer its penalty. If the backtrace list then adds up to exactly the operator's track length, they may use their Flow. If it exceeds their track length, the operator is overwhelmed and the game is lost.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Backtrace, Keyword::Flow],
+    },
+    Rule {
+        title: "Assist",
+        text: "Give your assist token to another operator. While they hold it they may also use your Skill. Tokens return to their owners at the end of the round.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Assist, Keyword::Skill],
+    },
+    Rule {
+        title: "Idle",
+        text: "Do nothing for the remainder of the round. Idling frees the operator from any penalty which was still preventing them from acting.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Idle],
+    },
+    Rule {
+        title: "Skills",
+        text: "Each operator has a Skill they may use whenever it applies, as may any operator holding their assist token.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Skill, Keyword::Assist],
+    },
+    Rule {
+        title: "Burnout",
+        text: "An operator who suffers Burnout receives a burnout token. An operator who suffers Burnout while they already have a token is overwhelmed and the game is lost.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Burnout],
+    },
+    Rule {
+        title: "Compromise",
+        text: "Take down a firewall. If no firewalls are left, take down a webservice instead - chosen by the operator suffering the penalty, or the first one remaining at the end of the round.",
+        phase: Phase::Turn,
+        keywords: &[Keyword::Compromise, Keyword::Firewall, Keyword::Webservice],
+    },
+    Rule {
+        title: "End of the round",
+        text: "Once every operator is idle, check each operator's secure slots. For each empty slot: no keyboard means a Compromise, no webservice takes down a webservice, and no database takes down a database. Then every virus hacker in the Breach causes a Compromise. Burned out operators enter Desperation, assist tokens return, and all secured and backtraced hackers are discarded.",
+        phase: Phase::RoundEnd,
+        keywords: &[
+            Keyword::Secure,
+            Keyword::Breach,
+            Keyword::Compromise,
+            Keyword::Webservice,
+            Keyword::Database,
+            Keyword::Desperation,
+            Keyword::Discard,
+        ],
+    },
+    Rule {
+        title: "Next round",
+        text: "The breach is shuffled back into the hacker stack, along with one lieutenant per operator for the second round or the bosses for the final round.",
+        phase: Phase::RoundEnd,
+        keywords: &[Keyword::Breach, Keyword::HackerStack],
+    },
+    Rule {
+        title: "Winning and losing",
+        text: "The operators win by surviving the end of the third round. They lose as soon as every webservice or every database is taken down, or an operator is overwhelmed.",
+        phase: Phase::GameEnd,
+        keywords: &[Keyword::Webservice, Keyword::Database],
+    },
+];
+
+/// Rules which involve the indicated keyword
+pub fn rules_for_keyword(keyword: Keyword) -> impl Iterator<Item = &'static Rule> {
+    RULES.iter().filter(move |x| x.keywords.contains(&keyword))
+}
+
+/// Rules which apply during the indicated phase
+pub fn rules_for_phase(phase: Phase) -> impl Iterator<Item = &'static Rule> {
+    RULES.iter().filter(move |x| x.phase == phase)
+}
+
+/// Rules relevant to facing the indicated hacker, including its penalty
+pub fn rules_for_hacker(hacker: HackerID) -> impl Iterator<Item = &'static Rule> {
+    let penalty = defs::hacker(hacker).penalty().keywords();
+    RULES.iter().filter(move |x| {
+        x.keywords
+            .iter()
+            .any(|k| *k == Keyword::Face || penalty.contains(k))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_by_keyword() {
+        let titles: Vec<&str> = rules_for_keyword(Keyword::Idle)
+            .map(|x| x.title())
+            .collect();
+        assert_eq!(titles, vec!["Taking a turn", "Idle"]);
+    }
+
+    #[test]
+    fn rules_by_phase() {
+        let titles: Vec<&str> = rules_for_phase(Phase::GameEnd).map(|x| x.title()).collect();
+        assert_eq!(titles, vec!["Winning and losing"]);
+    }
+
+    #[test]
+    fn rules_by_hacker() {
+        // 0: burnout penalty
+        let titles: Vec<&str> = rules_for_hacker(0).map(|x| x.title()).collect();
+        assert_eq!(titles, vec!["Taking a turn", "Face", "Burnout"]);
+    }
+
+    #[test]
+    fn changelog_covers_every_version() {
+        for version in 1..=RULES_VERSION {
+            assert!(CHANGELOG.iter().any(|x| x.version() == version));
+        }
+        assert!(CHANGELOG
+            .windows(2)
+            .all(|x| x[0].version() <= x[1].version()));
+        assert_eq!(CHANGELOG.last().unwrap().version(), RULES_VERSION);
+    }
+
+    #[test]
+    fn changes_since_version() {
+        assert_eq!(changes_since(0).count(), CHANGELOG.len());
+        let latest = CHANGELOG
+            .iter()
+            .filter(|x| x.version() == RULES_VERSION)
+            .count();
+        assert_eq!(changes_since(RULES_VERSION - 1).count(), latest);
+        assert_eq!(changes_since(RULES_VERSION).count(), 0);
+    }
+
+    #[test]
+    fn current_version_compatible() {
+        assert_eq!(check_rules_version(RULES_VERSION), Ok(()));
+    }
+
+    #[test]
+    fn newer_version_incompatible() {
+        assert_eq!(
+            check_rules_version(RULES_VERSION + 1),
+            Err(RulesVersionError::Newer(RULES_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn older_version_incompatible() {
+        assert_eq!(
+            check_rules_version(0),
+            Err(RulesVersionError::Incompatible(0))
+        );
+    }
+}
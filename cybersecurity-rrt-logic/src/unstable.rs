@@ -0,0 +1,5 @@
+//! Tooling built on the engine whose API may change between any two versions. Only
+//! available with the `unstable` feature - depend on it knowing upgrades may break you.
+pub use crate::game::coverage;
+pub use crate::game::export;
+pub use crate::game::tree;